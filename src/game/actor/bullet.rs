@@ -1,10 +1,10 @@
 use bevy::prelude::*;
 
-use crate::game::constants::ASSET_SCALE;
+use crate::game::constants::{ALLY_HITMASK, ASSET_SCALE};
 
 use super::super::scene::SceneAssets;
 
-use super::super::ai::AiMode;
+use super::super::ai::{AiBehavior, Movement};
 use super::super::components::*;
 use super::super::events::WeaponFiredEvent;
 use super::*;
@@ -14,11 +14,32 @@ pub enum BulletType {
     #[default]
     Standard,
     StandardEnemy,
+    Missile,     // Homes in on the nearest target
+    SineMissile, // Weaves along a sinusoidal path
+}
+
+impl BulletType {
+    // Distance travelled per step
+    pub fn speed(&self) -> f32 {
+        match self {
+            BulletType::Standard | BulletType::StandardEnemy => 14.1,
+            BulletType::Missile => 7.1,
+            BulletType::SineMissile => 8.5,
+        }
+    }
+
+    pub fn damage_kind(&self) -> DamageKind {
+        match self {
+            BulletType::Standard | BulletType::StandardEnemy => DamageKind::Energy,
+            BulletType::Missile | BulletType::SineMissile => DamageKind::Explosive,
+        }
+    }
 }
 
 #[derive(Bundle, Clone, Default)]
 pub struct BulletActorBundle {
     pub actor: Actor,
+    pub kinematics: Kinematics,
     pub scene_bundle: StarRustSceneBundle,
     pub collider: Collider,
     pub ai: Ai,
@@ -40,10 +61,15 @@ impl AiBulletBundle for StandardBullet {
         models: &Res<SceneAssets>,
         weapon_data: &WeaponFiredEvent,
     ) -> BulletActorBundle {
+        let speed = weapon_data.bullet_type.speed();
+        let forward = (weapon_data.rotation * Vec3::NEG_Z).truncate();
         return BulletActorBundle {
-            actor: Actor {
-                speed: Vec2::new(10.0, 10.0),
-            },
+            actor: Actor,
+            // Leaves the barrel at full speed and turns as sharply as it likes
+            kinematics: Kinematics::new(speed)
+                .with_acceleration(speed)
+                .with_drag(0.0)
+                .with_velocity(forward * speed),
             scene_bundle: StarRustSceneBundle {
                 scene: models.default_bullet.clone(),
                 transform: Transform::from_xyz(
@@ -58,12 +84,11 @@ impl AiBulletBundle for StandardBullet {
             collider: Collider {
                 hitmask: weapon_data.hitmask,
                 damage: 1,
-                rect: Vec2::new(0.2, 0.8),
-            },
-            ai: Ai {
-                mode: AiMode::ChargeForward1,
-                timer: Timer::default(),
+                damage_kind: weapon_data.bullet_type.damage_kind(),
+                // 0.2 x 0.8 on screen, sizes are in model units
+                shape: ColliderShape::Aabb(Vec2::new(0.2, 0.8) / ASSET_SCALE),
             },
+            ai: Ai::new(AiBehavior::single(Movement::Forward)),
             bullet: Bullet {},
             timed_despawn: TimedOobDespawn {
                 timer: Timer::from_seconds(0.05, TimerMode::Repeating),
@@ -84,3 +109,34 @@ impl AiBulletBundle for StandardEnemyBullet {
         return bullet;
     }
 }
+
+pub struct MissileBullet;
+
+impl AiBulletBundle for MissileBullet {
+    fn get_bullet_bundle(
+        models: &Res<SceneAssets>,
+        weapon_data: &WeaponFiredEvent,
+    ) -> BulletActorBundle {
+        let mut bullet = StandardBullet::get_bullet_bundle(models, weapon_data).clone();
+        if weapon_data.hitmask != ALLY_HITMASK {
+            bullet.scene_bundle.scene = models.default_enemy_bullet.clone();
+        }
+        return bullet;
+    }
+}
+
+pub struct SineMissileBullet;
+
+impl AiBulletBundle for SineMissileBullet {
+    fn get_bullet_bundle(
+        models: &Res<SceneAssets>,
+        weapon_data: &WeaponFiredEvent,
+    ) -> BulletActorBundle {
+        let mut bullet = MissileBullet::get_bullet_bundle(models, weapon_data).clone();
+        bullet.ai = Ai::new(AiBehavior::single(Movement::Sine {
+            amplitude: 1.5,
+            frequency: 1.0,
+        }));
+        return bullet;
+    }
+}
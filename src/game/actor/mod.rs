@@ -16,13 +16,17 @@ pub trait BundledActor<T: Bundle> {
         models: &Res<SceneAssets>,
         spawn_pos: Vec2,
     ) -> T;
+    // Randomness has to come from `rng`, so that the same seed spawns the same things
     fn spawn_bundle(
         commands: &mut Commands,
         audio_clips: &Res<AudioClipAssets>,
         models: &Res<SceneAssets>,
+        _rng: &fastrand::Rng,
         spawn_pos: Vec2,
-    ) {
-        commands.spawn(Self::get_bundle(audio_clips, models, spawn_pos));
+    ) -> Entity {
+        return commands
+            .spawn(Self::get_bundle(audio_clips, models, spawn_pos))
+            .id();
     }
 }
 
@@ -38,6 +42,7 @@ pub struct StarRustSceneBundle {
 #[derive(Bundle, Clone)]
 pub struct ActorBundle {
     pub actor: Actor,
+    pub kinematics: Kinematics,
     pub scene_bundle: StarRustSceneBundle,
     pub collider: Collider,
     pub health: Health,
@@ -49,6 +54,7 @@ pub struct ActorBundle {
 pub struct PlayerActorBundle {
     pub player: Player,
     pub actor_bundle: ActorBundle,
+    pub bomb: Bomb,
 }
 
 #[derive(Bundle, Clone)]
@@ -60,6 +66,15 @@ pub struct AiActorBundle {
     pub timed_oob_despawn: TimedOobDespawn,
 }
 
+// Slow moving hull without a weapon of its own, any firepower comes from mounted turrets
+#[derive(Bundle, Clone)]
+pub struct PlatformActorBundle {
+    pub actor_bundle: ActorBundle,
+    pub ai: Ai,
+    pub death_points_awarded: DeathPointsAwarded,
+    pub timed_oob_despawn: TimedOobDespawn,
+}
+
 pub struct ActorPlugin;
 impl Plugin for ActorPlugin {
     fn build(&self, app: &mut App) {
@@ -5,7 +5,10 @@ use super::super::actor::bullet::BulletType;
 use super::super::actor::BundledActor;
 use super::super::components::*;
 use super::*;
-use crate::game::constants::ASSET_SCALE;
+use crate::game::constants::{
+    ASSET_SCALE, BOMB_CHARGES, ENEMY_SCALE, PLATFORM_TURRET_OFFSETS, RIGHT_WALL,
+};
+use crate::game::turret::spawn_turret;
 use crate::game::AudioClipAssets;
 use crate::game::SceneAssets;
 use crate::game::{ALLY_HITMASK, ENEMY_HITMASK};
@@ -22,9 +25,8 @@ impl BundledActor<PlayerActorBundle> for PlayerShipDefault {
         let mut player_bundle = PlayerActorBundle {
             player: Player {},
             actor_bundle: ActorBundle {
-                actor: Actor {
-                    speed: Vec2::new(6.0, 6.0),
-                },
+                actor: Actor,
+                kinematics: Kinematics::new(6.0).with_acceleration(1.5).with_drag(0.1),
                 scene_bundle: StarRustSceneBundle {
                     scene: models.default_player.clone(),
                     transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
@@ -34,23 +36,27 @@ impl BundledActor<PlayerActorBundle> for PlayerShipDefault {
                 },
                 weapon: Weapon::new(
                     BulletType::Standard,
-                    Vec2::new(20.0, -10.0),
+                    Vec2::new(20.0, 0.0),
                     audio_clips.laser_shot.clone(),
                     0.15,
                 ),
                 collider: Collider {
-                    rect: Vec2::new(30.0, 30.0),
+                    shape: ColliderShape::Aabb(Vec2::new(30.0, 30.0) / ASSET_SCALE),
                     damage: 1,
                     hitmask: ALLY_HITMASK,
                     ..default()
                 },
                 health: Health {
                     hp: 20,
+                    max_hp: 20,
                     death_sound: audio_clips.light_explosion.clone(),
                     damage_sound: audio_clips.light_pow.clone(),
                 },
                 camera_shake_on_death: CameraShakeOnDeath { ..default() },
             },
+            bomb: Bomb {
+                charges: BOMB_CHARGES,
+            },
         };
         player_bundle.actor_bundle.weapon.cooldown_timer.pause();
         return player_bundle;
@@ -65,29 +71,26 @@ impl BundledActor<AiActorBundle> for DefaultEnemyShip {
         spawn_position: Vec2,
     ) -> AiActorBundle {
         return AiActorBundle {
-            ai: Ai {
-                mode: AiMode::ChargeForward1,
-                timer: Timer::default(),
-            },
+            ai: Ai::new(AiBehavior::single(Movement::Forward)),
             actor_bundle: ActorBundle {
-                actor: Actor {
-                    speed: Vec2::new(1.5, 1.5),
-                },
+                actor: Actor,
+                kinematics: Kinematics::new(2.1),
                 scene_bundle: StarRustSceneBundle {
                     scene: models.default_enemy.clone(),
                     transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
-                        .with_scale(Vec3::splat(23.0))
+                        .with_scale(Vec3::splat(ENEMY_SCALE))
                         .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
                     ..default()
                 },
                 collider: Collider {
-                    rect: Vec2::new(35.0, 35.0),
+                    shape: ColliderShape::Obb(Vec2::new(35.0, 35.0) / ENEMY_SCALE),
                     damage: 1,
                     hitmask: ENEMY_HITMASK,
                     ..default()
                 },
                 health: Health {
                     hp: 1,
+                    max_hp: 1,
                     death_sound: audio_clips.light_explosion.clone(),
                     damage_sound: audio_clips.no_sound.clone(),
                 },
@@ -115,87 +118,85 @@ impl BundledActor<AiActorBundle> for RaptorSineMovementVariant {
         spawn_position: Vec2,
     ) -> AiActorBundle {
         let mut variant = DefaultEnemyShip::get_bundle(audio_clips, models, spawn_position).clone();
-        variant.ai.mode = AiMode::Sinusoid1;
+        variant.ai = Ai::new(AiBehavior::single(Movement::Sine {
+            amplitude: 1.5,
+            frequency: 1.0,
+        }));
+        variant.actor_bundle.weapon.aim = AimMode::Aimed;
         return variant;
     }
 }
 
-pub struct JetCharger;
+pub struct MissileCarrier;
 
-impl BundledActor<AiActorBundle> for JetCharger {
+impl BundledActor<AiActorBundle> for MissileCarrier {
     fn get_bundle(
         audio_clips: &Res<AudioClipAssets>,
         models: &Res<SceneAssets>,
         spawn_position: Vec2,
     ) -> AiActorBundle {
         let mut variant = DefaultEnemyShip::get_bundle(audio_clips, models, spawn_position).clone();
-        variant.actor_bundle.scene_bundle.scene = models.jet_charger.clone();
-        variant.actor_bundle.actor.speed = Vec2::new(8.0, 8.0);
-        variant.ai.mode = AiMode::ChargeForward1;
-        // Disable Weapon
-        variant
-            .actor_bundle
-            .weapon
-            .cooldown_timer
-            .set_mode(TimerMode::Once);
-        variant
-            .actor_bundle
-            .weapon
-            .cooldown_timer
-            .set_duration(Duration::from_secs(300));
+        variant.actor_bundle.kinematics = Kinematics::new(1.4);
+        variant.actor_bundle.health.hp = 3;
+        variant.actor_bundle.health.max_hp = 3;
+        variant.actor_bundle.weapon = Weapon::new(
+            BulletType::Missile,
+            Vec2::new(20.0, 0.0),
+            audio_clips.sputter_rocket.clone(),
+            2.5,
+        );
+        // Hold position to fire, then leave, or leave early when badly damaged
+        variant.ai = Ai::new(AiBehavior::new(vec![
+            AiSegment::new(Movement::Enter {
+                x: RIGHT_WALL - 200.0,
+            })
+            .with_transition(AiCondition::HealthBelow(0.5), 2),
+            AiSegment::timed(
+                Movement::Hover {
+                    amplitude: 60.0,
+                    frequency: 0.8,
+                },
+                8.0,
+            )
+            .with_transition(AiCondition::HealthBelow(0.5), 2),
+            AiSegment::new(Movement::Retreat),
+        ]));
+        variant.death_points_awarded.points = 40;
         return variant;
     }
 }
 
-pub struct SpacePlatformBare;
+pub struct SineMissileRaptor;
 
-impl BundledActor<AiActorBundle> for SpacePlatformBare {
-    // FIXME: Create an WallAiActorBundle for this space platform
+impl BundledActor<AiActorBundle> for SineMissileRaptor {
     fn get_bundle(
         audio_clips: &Res<AudioClipAssets>,
         models: &Res<SceneAssets>,
         spawn_position: Vec2,
     ) -> AiActorBundle {
-        let mut variant = DefaultEnemyShip::get_bundle(audio_clips, models, spawn_position).clone();
-        variant.actor_bundle.scene_bundle.scene = models.space_platform.clone();
-        variant.actor_bundle.actor.speed = Vec2::new(2.0, 2.0);
-        variant.actor_bundle.health.hp = 100;
-        variant.actor_bundle.collider.rect = Vec2::new(210.0, 40.0);
-        variant.ai.mode = AiMode::ChargeForward1;
-        // Disable Weapon
-        variant
-            .actor_bundle
-            .weapon
-            .cooldown_timer
-            .set_mode(TimerMode::Once);
-        variant
-            .actor_bundle
-            .weapon
-            .cooldown_timer
-            .set_duration(Duration::from_secs(300));
+        let mut variant =
+            RaptorSineMovementVariant::get_bundle(audio_clips, models, spawn_position).clone();
+        variant.actor_bundle.weapon = Weapon::new(
+            BulletType::SineMissile,
+            Vec2::new(20.0, 0.0),
+            audio_clips.sputter_rocket.clone(),
+            1.2,
+        );
         return variant;
     }
 }
 
-// FIXME: Replace with dedicated powerup system, bundles, and spawnpoints!!
-pub struct Star;
+pub struct JetCharger;
 
-impl BundledActor<AiActorBundle> for Star {
+impl BundledActor<AiActorBundle> for JetCharger {
     fn get_bundle(
         audio_clips: &Res<AudioClipAssets>,
         models: &Res<SceneAssets>,
         spawn_position: Vec2,
     ) -> AiActorBundle {
         let mut variant = DefaultEnemyShip::get_bundle(audio_clips, models, spawn_position).clone();
-        variant.actor_bundle.camera_shake_on_death.magnitude = 0.0;
-        variant.actor_bundle.scene_bundle.scene = models.powerup_star.clone();
-        variant.actor_bundle.collider.damage = 0;
-        variant.death_points_awarded.points = 1;
-        variant.actor_bundle.actor.speed = Vec2::new(6.0, 6.0);
-        variant.actor_bundle.health.hp = 1;
-        variant.actor_bundle.health.death_sound = audio_clips.coin_larry.clone();
-        variant.actor_bundle.collider.rect = Vec2::new(10.0, 10.0);
-        variant.ai.mode = AiMode::ChargeForward1;
+        variant.actor_bundle.scene_bundle.scene = models.jet_charger.clone();
+        variant.actor_bundle.kinematics = Kinematics::new(11.3);
         // Disable Weapon
         variant
             .actor_bundle
@@ -210,3 +211,89 @@ impl BundledActor<AiActorBundle> for Star {
         return variant;
     }
 }
+
+pub struct SpacePlatformBare;
+
+impl BundledActor<PlatformActorBundle> for SpacePlatformBare {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PlatformActorBundle {
+        return PlatformActorBundle {
+            ai: Ai::new(AiBehavior::single(Movement::Forward)),
+            actor_bundle: ActorBundle {
+                actor: Actor,
+                // Heavy, slow to get going and slow to push around
+                kinematics: Kinematics::new(2.8).with_acceleration(0.1),
+                scene_bundle: StarRustSceneBundle {
+                    scene: models.space_platform.clone(),
+                    transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
+                        .with_scale(Vec3::splat(ENEMY_SCALE))
+                        .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
+                    ..default()
+                },
+                collider: Collider {
+                    shape: ColliderShape::Obb(Vec2::new(210.0, 40.0) / ENEMY_SCALE),
+                    damage: 1,
+                    hitmask: ENEMY_HITMASK,
+                    ..default()
+                },
+                health: Health {
+                    hp: 100,
+                    max_hp: 100,
+                    death_sound: audio_clips.light_explosion.clone(),
+                    damage_sound: audio_clips.no_sound.clone(),
+                },
+                // Never fired, platforms do not get AutoFire
+                weapon: Weapon::new(
+                    BulletType::StandardEnemy,
+                    Vec2::new(20.0, 0.0),
+                    audio_clips.laser_shot.clone(),
+                    0.5,
+                ),
+                camera_shake_on_death: CameraShakeOnDeath { ..default() },
+            },
+            death_points_awarded: DeathPointsAwarded { points: 20 },
+            timed_oob_despawn: TimedOobDespawn { ..default() },
+        };
+    }
+}
+
+pub struct TurretPlatform;
+
+impl BundledActor<PlatformActorBundle> for TurretPlatform {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PlatformActorBundle {
+        let mut variant = SpacePlatformBare::get_bundle(audio_clips, models, spawn_position);
+        variant.death_points_awarded.points = 60;
+        return variant;
+    }
+
+    // Spawns the hull along with its turrets
+    fn spawn_bundle(
+        commands: &mut Commands,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        _rng: &fastrand::Rng,
+        spawn_position: Vec2,
+    ) -> Entity {
+        let platform = commands
+            .spawn(Self::get_bundle(audio_clips, models, spawn_position))
+            .id();
+        for offset in PLATFORM_TURRET_OFFSETS {
+            spawn_turret(
+                commands,
+                platform,
+                offset,
+                audio_clips,
+                models,
+                spawn_position,
+            );
+        }
+        return platform;
+    }
+}
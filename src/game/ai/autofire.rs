@@ -1,48 +1,51 @@
 use crate::game::events::AudioEvent;
 
-use super::super::super::AppState;
-use super::super::collisions::CollisionEvent;
-use super::super::components::{AutoFire, Collider, Weapon};
+use super::super::components::{AutoFire, Collider, Kinematics, Player, Weapon};
+use super::super::events::CollisionEvent;
 use super::super::events::WeaponFiredEvent;
-use bevy::{prelude::*, time::*};
+use super::super::resources::SimulationTime;
+use super::super::simulation::{SimulationApp, SimulationStep};
+use super::super::weapon::{aimed_transform, volley_events};
+use bevy::prelude::*;
 
 pub struct AutoFirePlugin;
 
 // Plugin definition
 impl Plugin for AutoFirePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<WeaponFiredEvent>()
-            .add_event::<CollisionEvent>()
-            .add_system_set(SystemSet::on_enter(AppState::InGame))
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
-                    //.with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-                    .with_system(fire_controller),
-            );
+        app.add_simulation_event::<WeaponFiredEvent>()
+            .add_simulation_event::<CollisionEvent>()
+            .add_simulation_system(SimulationStep::Weapons, fire_controller);
     }
 }
 
 // Fire controller system
 pub fn fire_controller(
-    time: Res<Time>,
+    time: Res<SimulationTime>,
     mut bullet_fired_event: EventWriter<WeaponFiredEvent>,
     mut audio_event: EventWriter<AudioEvent>,
     mut query: Query<(&Transform, &Collider, &mut Weapon), With<AutoFire>>,
+    player_query: Query<(&Transform, &Kinematics), (With<Player>, Without<AutoFire>)>,
 ) {
+    let target = player_query
+        .iter()
+        .next()
+        .map(|(transform, kinematics)| (transform.translation.truncate(), kinematics.velocity));
     for (transform, collider, mut weapon) in &mut query {
         // ref: https://bevy-cheatbook.github.io/features/time.html
         weapon.cooldown_timer.tick(time.delta());
+
+        let mut fire = weapon.tick_burst(time.delta());
         if weapon.cooldown_timer.finished() {
-            let event = WeaponFiredEvent {
-                bullet_type: weapon.bullet_type.clone(),
-                translation: Vec2::new(
-                    transform.translation.x + weapon.offset.x * transform.forward().x,
-                    transform.translation.y + weapon.offset.y * transform.forward().y,
-                ),
-                rotation: transform.rotation,
-                hitmask: collider.hitmask, // Hurt player only
-            };
-            bullet_fired_event.send(event);
+            weapon.start_burst();
+            fire = true;
+        }
+        if fire {
+            // Hurt player only
+            let aimed = aimed_transform(&mut weapon, transform, target);
+            for event in volley_events(&weapon, &aimed, collider.hitmask) {
+                bullet_fired_event.send(event);
+            }
             audio_event.send(AudioEvent {
                 clip: weapon.firing_audio_clip.clone(),
             })
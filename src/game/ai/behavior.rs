@@ -0,0 +1,111 @@
+// Data-driven AI: a behavior is a list of movement segments, each running for
+// an optional duration and leaving early when one of its transitions fires.
+use std::sync::Arc;
+
+// What an actor does every frame while a segment is active
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Movement {
+    Still,
+    // Straight along the actor's forward direction at its max speed
+    Forward,
+    // Forward while weaving; amplitude is the sideways speed per step
+    Sine { amplitude: f32, frequency: f32 },
+    // Forward until reaching `x`, the segment then ends by itself
+    Enter { x: f32 },
+    // Bob up and down in place
+    Hover { amplitude: f32, frequency: f32 },
+    // Sweep up and down across the arena at the actor's max speed
+    Strafe,
+    // Back off the way the actor came in
+    Retreat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AiCondition {
+    PlayerCloserThan(f32),
+    PlayerFartherThan(f32),
+    // Fraction of max_hp
+    HealthBelow(f32),
+}
+
+#[derive(Clone, Debug)]
+pub struct AiTransition {
+    pub condition: AiCondition,
+    pub goto: usize, // Index of the segment to switch to
+}
+
+#[derive(Clone, Debug)]
+pub struct AiSegment {
+    pub movement: Movement,
+    pub duration: Option<f32>, // Seconds, runs until a transition fires when None
+    pub transitions: Vec<AiTransition>,
+}
+
+impl AiSegment {
+    pub fn new(movement: Movement) -> Self {
+        return AiSegment {
+            movement: movement,
+            duration: None,
+            transitions: Vec::new(),
+        };
+    }
+
+    pub fn timed(movement: Movement, duration: f32) -> Self {
+        let mut segment = AiSegment::new(movement);
+        segment.duration = Some(duration);
+        return segment;
+    }
+
+    pub fn with_transition(mut self, condition: AiCondition, goto: usize) -> Self {
+        self.transitions.push(AiTransition {
+            condition: condition,
+            goto: goto,
+        });
+        return self;
+    }
+}
+
+// Segments run in order, the last one either loops back to the start or keeps running.
+// Shared between every actor spawned with it.
+#[derive(Clone, Debug)]
+pub struct AiBehavior {
+    pub segments: Arc<Vec<AiSegment>>,
+    pub looping: bool,
+}
+
+impl AiBehavior {
+    pub fn new(segments: Vec<AiSegment>) -> Self {
+        return AiBehavior {
+            segments: Arc::new(segments),
+            looping: false,
+        };
+    }
+
+    pub fn looping(segments: Vec<AiSegment>) -> Self {
+        let mut behavior = AiBehavior::new(segments);
+        behavior.looping = true;
+        return behavior;
+    }
+
+    // A single movement, kept up forever
+    pub fn single(movement: Movement) -> Self {
+        return AiBehavior::new(vec![AiSegment::new(movement)]);
+    }
+
+    // Segment that follows `index` once it runs out
+    pub fn next(&self, index: usize) -> usize {
+        if index + 1 < self.segments.len() {
+            return index + 1;
+        }
+        if self.looping {
+            return 0;
+        }
+        return index;
+    }
+}
+
+impl Default for AiBehavior {
+    fn default() -> Self {
+        return AiBehavior::single(Movement::Still);
+    }
+}
@@ -1,53 +1,270 @@
-use super::collisions::check_collisions;
 use super::components::*;
-use super::{super::AppState, constants::TIME_STEP};
-use bevy::{prelude::*, time::FixedTimestep};
+use super::constants::{FORMATION_STIFFNESS, HAZARD_HITMASK, TOP_WALL};
+use super::resources::SimulationTime;
+use super::simulation::{SimulationApp, SimulationStep};
+use bevy::prelude::*;
 pub mod autofire;
 use autofire::AutoFirePlugin;
-
-#[derive(Copy, Clone, Default)]
-pub enum AiMode {
-    #[default]
-    NoMovement,
-    ChargeForward1,
-    Sinusoid1,
-}
+pub mod behavior;
+pub use behavior::*;
 
 pub struct AiPlugin;
 
 impl Plugin for AiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(AutoFirePlugin).add_system_set(
-            SystemSet::on_update(AppState::InGame)
-                //.with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-                .with_system(update_ai.before(check_collisions)),
+        app.add_plugin(AutoFirePlugin).add_simulation_system_set(
+            SimulationStep::Ai,
+            SystemSet::new()
+                .with_system(home_in_on_targets.before(update_ai))
+                .with_system(update_ai)
+                .with_system(follow_paths.before(hold_formation))
+                .with_system(hold_formation.after(update_ai)),
         );
     }
 }
 
-fn update_ai(time: Res<Time>, mut query: Query<(&mut Transform, &Actor, &mut Ai), With<Ai>>) {
-    for (transform, actor, mut ai) in &mut query {
+fn update_ai(
+    time: Res<SimulationTime>,
+    mut query: Query<
+        (&Transform, &mut Kinematics, &mut Ai, Option<&Health>),
+        (Without<FormationSlot>, Without<FollowPath>),
+    >,
+    player_query: Query<&Transform, (With<Player>, Without<Ai>)>,
+) {
+    let player = player_query
+        .iter()
+        .next()
+        .map(|transform| transform.translation.truncate());
+    for (transform, mut kinematics, mut ai, health) in &mut query {
         ai.timer.tick(time.delta());
-        match ai.mode {
-            AiMode::NoMovement => {}
-            AiMode::ChargeForward1 => charge_forward(transform, actor.speed.length()),
-            AiMode::Sinusoid1 => sine_charge(&time, transform, actor.speed.length(), 1.5, 1.0),
+        let behavior = ai.behavior.clone();
+        let segment = &behavior.segments[ai.segment];
+        let speed = kinematics.max_speed;
+
+        // Every movement asks for a velocity, the actor steers towards it as well as it can
+        let (desired, arrived) = match segment.movement {
+            Movement::Still => (Vec2::ZERO, false),
+            Movement::Forward => (charge_forward(&transform, speed), false),
+            Movement::Sine {
+                amplitude,
+                frequency,
+            } => (
+                sine_charge(&time, &transform, speed, amplitude, frequency),
+                false,
+            ),
+            Movement::Enter { x } => enter(&transform, speed, x),
+            Movement::Hover {
+                amplitude,
+                frequency,
+            } => (hover(&time, &transform, amplitude, frequency), false),
+            Movement::Strafe => (strafe(&transform, &mut ai.strafe_direction, speed), false),
+            Movement::Retreat => (charge_forward(&transform, -speed), false),
+        };
+        kinematics.steer(desired);
+
+        // Transitions take priority over the segment running out
+        let position = transform.translation.truncate();
+        let transition = segment
+            .transitions
+            .iter()
+            .find(|transition| condition_met(transition.condition, position, player, health));
+        let next = match transition {
+            Some(transition) => transition.goto,
+            None if arrived || (segment.duration.is_some() && ai.timer.finished()) => {
+                behavior.next(ai.segment)
+            }
+            None => ai.segment,
+        };
+        if next != ai.segment {
+            ai.enter(next);
         }
     }
 }
 
-fn charge_forward(mut t: Mut<Transform>, speed: f32) {
-    t.translation = t.translation + speed * t.forward();
+fn follow_paths(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, &mut Kinematics, &mut FollowPath)>,
+) {
+    for (entity, transform, mut kinematics, mut path) in &mut query {
+        let position = transform.translation.truncate();
+        // A point counts as passed once it is within a step's reach, closely
+        // sampled points can be passed several at a time
+        while path.index < path.points.len()
+            && path.points[path.index].distance(position) <= kinematics.max_speed
+        {
+            path.index += 1;
+        }
+        match path.points.get(path.index) {
+            Some(target) => {
+                let desired = (*target - position).normalize_or_zero() * kinematics.max_speed;
+                kinematics.steer(desired);
+            }
+            None => {
+                commands.entity(entity).remove::<FollowPath>();
+            }
+        }
+    }
+}
+
+// Members match their leader's velocity, and close in on their slot on top of it
+fn hold_formation(
+    mut commands: Commands,
+    mut member_query: Query<(Entity, &mut Transform, &mut Kinematics, &FormationSlot)>,
+    leader_query: Query<(&Transform, Option<&Kinematics>), Without<FormationSlot>>,
+) {
+    for (entity, mut transform, mut kinematics, slot) in &mut member_query {
+        match leader_query.get(slot.leader) {
+            Ok((leader_transform, leader_kinematics)) => {
+                let slot_position = leader_transform.translation.truncate() + slot.offset;
+                let leader_velocity = leader_kinematics.map_or(Vec2::ZERO, |k| k.velocity);
+                let correction =
+                    (slot_position - transform.translation.truncate()) * FORMATION_STIFFNESS;
+                kinematics.steer(leader_velocity + correction);
+                transform.rotation = leader_transform.rotation;
+            }
+            // Leader is gone, carry on alone
+            Err(_) => {
+                commands.entity(entity).remove::<FormationSlot>();
+            }
+        }
+    }
+}
+
+fn condition_met(
+    condition: AiCondition,
+    position: Vec2,
+    player: Option<Vec2>,
+    health: Option<&Health>,
+) -> bool {
+    match condition {
+        AiCondition::PlayerCloserThan(distance) => {
+            player.map_or(false, |player| player.distance(position) < distance)
+        }
+        AiCondition::PlayerFartherThan(distance) => {
+            player.map_or(false, |player| player.distance(position) > distance)
+        }
+        AiCondition::HealthBelow(fraction) => health.map_or(false, |health| {
+            (health.hp as f32) < fraction * health.max_hp as f32
+        }),
+    }
+}
+
+fn charge_forward(t: &Transform, speed: f32) -> Vec2 {
+    return speed * t.forward().truncate();
 }
 
 fn sine_charge(
-    time: &Res<Time>,
-    mut t: Mut<Transform>,
+    time: &Res<SimulationTime>,
+    t: &Transform,
     forward_speed: f32,
     amplitude: f32,
     frequency: f32,
+) -> Vec2 {
+    let up_down = t.up() * amplitude * (time.elapsed_seconds() * frequency).sin();
+    return charge_forward(t, forward_speed) + up_down.truncate();
+}
+
+// Arrives once the actor has flown in past x
+fn enter(t: &Transform, speed: f32, x: f32) -> (Vec2, bool) {
+    if t.translation.x <= x {
+        return (Vec2::ZERO, true);
+    }
+    return (charge_forward(t, speed), false);
+}
+
+// Follows the derivative of the sine so switching segments does not jolt the actor
+fn hover(time: &Res<SimulationTime>, t: &Transform, amplitude: f32, frequency: f32) -> Vec2 {
+    let mut velocity = amplitude * frequency * (time.elapsed_seconds() * frequency).cos();
+    // Never further out than the limit
+    let limit = TOP_WALL - 100.0;
+    if t.translation.y.abs() >= limit && velocity * t.translation.y > 0.0 {
+        velocity = 0.0;
+    }
+    return Vec2::new(0.0, velocity * time.delta_seconds());
+}
+
+// Bounces between the top and bottom of the arena
+fn strafe(t: &Transform, direction: &mut f32, speed: f32) -> Vec2 {
+    let limit = TOP_WALL - 100.0;
+    if t.translation.y.abs() >= limit {
+        *direction = -t.translation.y.signum();
+    }
+    return Vec2::new(0.0, *direction * speed);
+}
+
+fn home_in_on_targets(
+    time: Res<SimulationTime>,
+    mut missile_query: Query<(&mut Transform, &Collider, &Homing)>,
+    target_query: Query<(&Transform, &Collider), (With<Health>, Without<Homing>)>,
 ) {
-    let forward = t.translation + forward_speed * t.forward();
-    let up_down = t.up() * amplitude * (time.elapsed_seconds() as f32 * frequency).sin();
-    t.translation = forward + up_down;
+    for (mut transform, collider, homing) in &mut missile_query {
+        let position = transform.translation.truncate();
+        let nearest = target_query
+            .iter()
+            .filter(|(_, target_collider)| {
+                // Missiles chase the other side, not asteroids
+                target_collider.hitmask != collider.hitmask
+                    && target_collider.hitmask != HAZARD_HITMASK
+            })
+            .map(|(target_transform, _)| target_transform.translation.truncate())
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        if let Some(target) = nearest {
+            transform.rotation = steer_towards(
+                transform.rotation,
+                position,
+                target,
+                homing.turn_rate * time.delta_seconds(),
+            );
+        }
+    }
+}
+
+// Rotates about the z axis so that the forward direction turns towards target,
+// by no more than max_angle radians
+pub fn steer_towards(rotation: Quat, position: Vec2, target: Vec2, max_angle: f32) -> Quat {
+    let forward = (rotation * Vec3::NEG_Z).truncate();
+    let desired = target - position;
+    if forward.length_squared() == 0.0 || desired.length_squared() == 0.0 {
+        return rotation;
+    }
+    let angle = forward.angle_between(desired);
+    return Quat::from_rotation_z(angle.clamp(-max_angle, max_angle)) * rotation;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::actor::bullet::BulletType;
+    use super::super::constants::{MISSILE_TURN_RATE, TIME_STEP};
+    use super::*;
+
+    fn facing(direction: Vec3) -> Quat {
+        return Quat::from_rotation_arc(Vec3::NEG_Z, direction);
+    }
+
+    #[test]
+    fn steering_turns_no_further_than_max_angle() {
+        let rotation = steer_towards(facing(Vec3::Y), Vec2::ZERO, Vec2::new(100.0, 0.0), 0.1);
+        let forward = (rotation * Vec3::NEG_Z).truncate();
+        assert!((forward.angle_between(Vec2::Y).abs() - 0.1).abs() < 1e-4);
+        assert!(forward.x > 0.0);
+    }
+
+    #[test]
+    fn homing_missile_converges_on_a_stationary_target() {
+        let target = Vec2::new(600.0, 200.0);
+        let speed = BulletType::Missile.speed();
+        let mut rotation = facing(Vec3::Y);
+        let mut position = Vec2::ZERO;
+        let start = position.distance(target);
+        let mut closest = start;
+        for _ in 0..600 {
+            rotation = steer_towards(rotation, position, target, MISSILE_TURN_RATE * TIME_STEP);
+            position += speed * (rotation * Vec3::NEG_Z).truncate();
+            closest = closest.min(position.distance(target));
+        }
+        assert!(closest < speed, "closest approach {} of {}", closest, start);
+    }
 }
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+
+use super::actor::{BundledActor, StarRustSceneBundle};
+use super::components::*;
+use super::constants::*;
+use super::health::apply_damage;
+use super::resources::{GameRng, SimulationTime};
+use super::simulation::{SimulationApp, SimulationStep};
+use super::{AudioClipAssets, SceneAssets};
+
+pub struct AsteroidPlugin;
+
+impl Plugin for AsteroidPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(SimulationStep::Ai, drift)
+            .add_simulation_system(SimulationStep::Damage, split_asteroids.after(apply_damage));
+    }
+}
+
+#[derive(Bundle, Clone)]
+pub struct AsteroidBundle {
+    pub asteroid: Asteroid,
+    pub drift: Drift,
+    pub actor: Actor,
+    pub kinematics: Kinematics,
+    pub scene_bundle: StarRustSceneBundle,
+    pub collider: Collider,
+    pub health: Health,
+    pub death_points_awarded: DeathPointsAwarded,
+    pub camera_shake_on_death: CameraShakeOnDeath,
+    pub timed_oob_despawn: TimedOobDespawn,
+}
+
+impl AsteroidBundle {
+    pub fn new(
+        size: u32,
+        drift: Drift,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> Self {
+        let hp = ASTEROID_HP[size as usize - 1];
+        return AsteroidBundle {
+            asteroid: Asteroid { size: size },
+            drift: drift,
+            actor: Actor,
+            kinematics: Kinematics::drifting(drift.velocity),
+            scene_bundle: StarRustSceneBundle {
+                scene: models.asteroid.clone(),
+                transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
+                    .with_scale(Vec3::splat(ASTEROID_SCALE * size as f32)),
+                ..default()
+            },
+            collider: Collider {
+                shape: ColliderShape::Circle(11.0 / ASTEROID_SCALE),
+                damage: 2,
+                damage_kind: DamageKind::Kinetic,
+                hitmask: HAZARD_HITMASK,
+            },
+            health: Health {
+                hp: hp,
+                max_hp: hp,
+                death_sound: audio_clips.light_explosion.clone(),
+                damage_sound: audio_clips.light_pow.clone(),
+            },
+            death_points_awarded: DeathPointsAwarded {
+                points: 10 * size as i32,
+            },
+            camera_shake_on_death: CameraShakeOnDeath {
+                magnitude: 5.0 * size as f32,
+                ..default()
+            },
+            timed_oob_despawn: TimedOobDespawn { ..default() },
+        };
+    }
+}
+
+impl Drift {
+    // Mostly leftwards, with a little vertical wander and a random tumble
+    pub fn random(rng: &fastrand::Rng) -> Self {
+        return Drift {
+            velocity: Vec2::new(-1.0 - 2.0 * rng.f32(), 2.0 * rng.f32() - 1.0),
+            spin_axis: Vec3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5)
+                .try_normalize()
+                .unwrap_or(Vec3::Z),
+            spin: 0.5 + 2.0 * rng.f32(),
+        };
+    }
+
+    // Middle of the random range, for bundles built without an rng at hand
+    pub fn steady() -> Self {
+        return Drift {
+            velocity: Vec2::new(-2.0, 0.0),
+            spin_axis: Vec3::Z,
+            spin: 1.5,
+        };
+    }
+}
+
+pub struct LargeAsteroid;
+
+impl BundledActor<AsteroidBundle> for LargeAsteroid {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> AsteroidBundle {
+        return AsteroidBundle::new(
+            ASTEROID_MAX_SIZE,
+            Drift::steady(),
+            audio_clips,
+            models,
+            spawn_position,
+        );
+    }
+
+    fn spawn_bundle(
+        commands: &mut Commands,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        rng: &fastrand::Rng,
+        spawn_position: Vec2,
+    ) -> Entity {
+        let mut bundle = Self::get_bundle(audio_clips, models, spawn_position);
+        bundle.drift = Drift::random(rng);
+        bundle.kinematics = Kinematics::drifting(bundle.drift.velocity);
+        return commands.spawn(bundle).id();
+    }
+}
+
+// A spread of asteroids of mixed sizes across the full height of the arena
+pub struct AsteroidField;
+
+impl BundledActor<AsteroidBundle> for AsteroidField {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> AsteroidBundle {
+        return LargeAsteroid::get_bundle(audio_clips, models, spawn_position);
+    }
+
+    fn spawn_bundle(
+        commands: &mut Commands,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        rng: &fastrand::Rng,
+        spawn_position: Vec2,
+    ) -> Entity {
+        // The field as a whole is represented by its first asteroid
+        let mut first = None;
+        for _ in 0..ASTEROID_FIELD_COUNT {
+            let position = Vec2::new(
+                spawn_position.x - 150.0 * rng.f32(),
+                BOTTOM_WALL + (TOP_WALL - BOTTOM_WALL) * rng.f32(),
+            );
+            let asteroid = commands
+                .spawn(AsteroidBundle::new(
+                    rng.u32(1..=ASTEROID_MAX_SIZE),
+                    Drift::random(rng),
+                    audio_clips,
+                    models,
+                    position,
+                ))
+                .id();
+            first = first.or(Some(asteroid));
+        }
+        return first.unwrap();
+    }
+}
+
+// Asteroids coast on their own, only the tumble needs doing
+fn drift(mut query: Query<(&mut Transform, &Drift)>, time: Res<SimulationTime>) {
+    for (mut transform, drift) in &mut query {
+        transform.rotate(Quat::from_axis_angle(
+            drift.spin_axis,
+            drift.spin * time.delta_seconds(),
+        ));
+    }
+}
+
+// Destroyed asteroids break up into smaller ones flying apart
+fn split_asteroids(
+    mut commands: Commands,
+    audio_clips: Res<AudioClipAssets>,
+    models: Res<SceneAssets>,
+    mut game_rng: ResMut<GameRng>,
+    query: Query<(&Transform, &Health, &Asteroid, &Kinematics), Changed<Health>>,
+) {
+    let rng = game_rng.gameplay.fork();
+    for (transform, health, asteroid, kinematics) in &query {
+        if health.hp > 0 || asteroid.size <= 1 {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        for side in [-1.0, 1.0] {
+            let mut fragment_drift = Drift::random(&rng);
+            fragment_drift.velocity =
+                kinematics.velocity + Vec2::new(0.0, side * (0.5 + rng.f32()));
+            commands.spawn(AsteroidBundle::new(
+                asteroid.size - 1,
+                fragment_drift,
+                &audio_clips,
+                &models,
+                position + Vec2::new(0.0, side * 10.0 * asteroid.size as f32),
+            ));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// Headless stress test of check_collisions.
+// Runs a single collision step over a crowded arena with each broadphase.
+use bevy::{prelude::*, utils::Duration};
+use std::time::Instant;
+
+use super::collisions::{check_collisions, collision_damage};
+use super::components::*;
+use super::constants::*;
+use super::events::*;
+use super::health::apply_damage;
+use super::resources::Broadphase;
+
+const BENCH_ACTORS: usize = 2000;
+const BENCH_BULLETS: usize = 5000;
+const BENCH_ROUNDS: u64 = 5;
+
+// What a collision step left behind, compared between broadphases
+#[derive(Debug, PartialEq, Eq)]
+struct BenchOutcome {
+    hits: usize,
+    survivors: usize,
+    hp_left: i32,
+}
+
+/// Run with `cargo test --release -- --ignored --nocapture bench_collisions`
+#[test]
+#[ignore]
+fn bench_collisions() {
+    println!(
+        "{} actors, {} bullets, {} rounds",
+        BENCH_ACTORS, BENCH_BULLETS, BENCH_ROUNDS
+    );
+    for broadphase in [Broadphase::BruteForce, Broadphase::Grid] {
+        let mut total = Duration::ZERO;
+        for round in 0..BENCH_ROUNDS {
+            let mut app = bench_app(broadphase, round);
+            let start = Instant::now();
+            app.update();
+            total += start.elapsed();
+        }
+        println!(
+            "{:?}: {:.2} ms per step",
+            broadphase,
+            total.as_secs_f64() * 1000.0 / BENCH_ROUNDS as f64
+        );
+    }
+}
+
+// The grid only skips pairs that can't touch, so it finds exactly the same hits
+#[test]
+fn grid_matches_brute_force() {
+    for round in 0..BENCH_ROUNDS {
+        let mut brute_force = bench_app(Broadphase::BruteForce, round);
+        brute_force.update();
+        let mut grid = bench_app(Broadphase::Grid, round);
+        grid.update();
+        let expected = outcome(&mut brute_force);
+        assert!(expected.hits > 0);
+        assert_eq!(outcome(&mut grid), expected, "round {}", round);
+    }
+}
+
+// World with only collisions running, filled from a seed so every broadphase sees the same arena
+fn bench_app(broadphase: Broadphase, seed: u64) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(broadphase)
+        .add_event::<CollisionEvent>()
+        .add_event::<DamageEvent>()
+        .add_event::<DeathEvent>()
+        .add_event::<AudioEvent>()
+        .add_system(check_collisions)
+        .add_system(collision_damage.after(check_collisions))
+        .add_system(apply_damage.after(collision_damage));
+
+    let rng = fastrand::Rng::with_seed(seed);
+    let random_position = || {
+        return Transform::from_xyz(
+            LEFT_WALL + (RIGHT_WALL - LEFT_WALL) * rng.f32(),
+            BOTTOM_WALL + (TOP_WALL - BOTTOM_WALL) * rng.f32(),
+            0.0,
+        );
+    };
+    for _ in 0..BENCH_ACTORS {
+        app.world.spawn((
+            Actor,
+            random_position(),
+            Collider {
+                shape: ColliderShape::Aabb(Vec2::new(40.0, 40.0)),
+                damage: 1,
+                damage_kind: DamageKind::Kinetic,
+                hitmask: ENEMY_HITMASK,
+            },
+            Health {
+                hp: 3,
+                max_hp: 3,
+                death_sound: Handle::default(),
+                damage_sound: Handle::default(),
+            },
+            DeathPointsAwarded { points: 10 },
+        ));
+    }
+    for _ in 0..BENCH_BULLETS {
+        app.world.spawn((
+            Bullet,
+            random_position(),
+            Collider {
+                shape: ColliderShape::Obb(Vec2::new(10.0, 4.0)),
+                damage: 1,
+                damage_kind: DamageKind::Energy,
+                hitmask: ALLY_HITMASK,
+            },
+        ));
+    }
+    return app;
+}
+
+fn outcome(app: &mut App) -> BenchOutcome {
+    let hits = app.world.resource::<Events<CollisionEvent>>().len();
+    let mut query = app.world.query::<&Health>();
+    return BenchOutcome {
+        hits: hits,
+        survivors: query
+            .iter(&app.world)
+            .filter(|health| health.hp > 0)
+            .count(),
+        hp_left: query.iter(&app.world).map(|health| health.hp).sum(),
+    };
+}
@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+
+use super::actor::bullet::BulletType;
+use super::actor::{ActorBundle, BundledActor, StarRustSceneBundle};
+use super::ai::{AiBehavior, AiSegment, Movement};
+use super::components::*;
+use super::constants::*;
+use super::health::apply_damage;
+use super::simulation::{SimulationApp, SimulationStep};
+use super::turret::spawn_turret;
+use super::{AudioClipAssets, SceneAssets};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system_set(
+            SimulationStep::Damage,
+            SystemSet::new()
+                .with_system(expose_boss_core.after(apply_damage))
+                .with_system(update_boss_phase.after(apply_damage)),
+        );
+    }
+}
+
+#[derive(Bundle, Clone)]
+pub struct BossBundle {
+    pub actor_bundle: ActorBundle,
+    pub ai: Ai,
+    pub auto_fire: AutoFire,
+    pub death_points_awarded: DeathPointsAwarded,
+    pub boss: Boss,
+    pub armor: Armor,
+    pub invulnerable: Invulnerable,
+}
+
+pub struct BasicBoss;
+
+impl BasicBoss {
+    fn phases() -> Vec<BossPhase> {
+        let offset = Vec2::new(60.0, 0.0);
+        let mut strafe_pattern = FiringPattern::cone(offset, 5, 50.0_f32.to_radians());
+        strafe_pattern.burst_count = 2;
+        strafe_pattern.burst_delay = 0.15;
+        // Evenly spaced ring of 8 shots, rotated a little every volley
+        let mut desperate_pattern =
+            FiringPattern::cone(Vec2::ZERO, 8, std::f32::consts::TAU * 7.0 / 8.0);
+        desperate_pattern.burst_count = 3;
+        desperate_pattern.burst_delay = 0.1;
+        return vec![
+            BossPhase {
+                hp_fraction: 1.0,
+                behavior: AiBehavior::new(vec![
+                    AiSegment::new(Movement::Enter { x: BOSS_HOVER_X }),
+                    AiSegment::new(Movement::Hover {
+                        amplitude: 100.0,
+                        frequency: 0.5,
+                    }),
+                ]),
+                pattern: FiringPattern::cone(offset, 3, 30.0_f32.to_radians()),
+                aim: AimMode::Aimed,
+                cooldown: 1.5,
+            },
+            BossPhase {
+                hp_fraction: 0.6,
+                behavior: AiBehavior::single(Movement::Strafe),
+                pattern: strafe_pattern,
+                aim: AimMode::Predictive,
+                cooldown: 1.0,
+            },
+            BossPhase {
+                hp_fraction: 0.3,
+                // Alternate between sweeping the arena and a wide fast bob
+                behavior: AiBehavior::looping(vec![
+                    AiSegment::timed(Movement::Strafe, 4.0),
+                    AiSegment::timed(
+                        Movement::Hover {
+                            amplitude: 250.0,
+                            frequency: 1.2,
+                        },
+                        4.0,
+                    ),
+                ]),
+                pattern: desperate_pattern,
+                aim: AimMode::Spiral { step: 0.2 },
+                cooldown: 0.7,
+            },
+        ];
+    }
+}
+
+impl BundledActor<BossBundle> for BasicBoss {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> BossBundle {
+        let phases = BasicBoss::phases();
+        let mut weapon = Weapon::new(
+            BulletType::StandardEnemy,
+            Vec2::ZERO,
+            audio_clips.laser_shot.clone(),
+            phases[0].cooldown,
+        );
+        weapon.pattern = phases[0].pattern.clone();
+        weapon.aim = phases[0].aim;
+        return BossBundle {
+            actor_bundle: ActorBundle {
+                actor: Actor,
+                kinematics: Kinematics::new(2.1),
+                scene_bundle: StarRustSceneBundle {
+                    scene: models.basic_boss.clone(),
+                    transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
+                        .with_scale(Vec3::splat(30.0))
+                        .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
+                    ..default()
+                },
+                collider: Collider {
+                    shape: ColliderShape::Circle(2.0),
+                    damage: 1,
+                    hitmask: ENEMY_HITMASK,
+                    ..default()
+                },
+                health: Health {
+                    hp: 60,
+                    max_hp: 60,
+                    death_sound: audio_clips.salt_explosion.clone(),
+                    damage_sound: audio_clips.light_pow.clone(),
+                },
+                weapon: weapon,
+                camera_shake_on_death: CameraShakeOnDeath {
+                    magnitude: 60.0,
+                    duration_secs: 0.8,
+                },
+            },
+            ai: Ai::new(phases[0].behavior.clone()),
+            auto_fire: AutoFire {},
+            death_points_awarded: DeathPointsAwarded { points: 1000 },
+            boss: Boss {
+                phases: phases,
+                phase: 0,
+            },
+            // Too heavy to ram, and its plating takes the edge off bombs
+            armor: Armor {
+                kinetic: 1.0,
+                explosive: 0.5,
+                ..default()
+            },
+            invulnerable: Invulnerable,
+        };
+    }
+
+    // Spawns the core along with its turrets
+    fn spawn_bundle(
+        commands: &mut Commands,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        _rng: &fastrand::Rng,
+        spawn_position: Vec2,
+    ) -> Entity {
+        let boss = commands
+            .spawn(Self::get_bundle(audio_clips, models, spawn_position))
+            .id();
+        for offset in BOSS_TURRET_OFFSETS {
+            spawn_turret(commands, boss, offset, audio_clips, models, spawn_position);
+        }
+        return boss;
+    }
+}
+
+// The core can be damaged once all of its turrets are destroyed
+fn expose_boss_core(
+    mut commands: Commands,
+    boss_query: Query<Entity, (With<Boss>, With<Invulnerable>)>,
+    turret_query: Query<&Turret>,
+) {
+    for boss in &boss_query {
+        if !turret_query.iter().any(|turret| turret.anchor == boss) {
+            commands.entity(boss).remove::<Invulnerable>();
+        }
+    }
+}
+
+// Each new phase starts behind a short-lived shield
+fn update_boss_phase(
+    mut commands: Commands,
+    mut boss_query: Query<(Entity, &mut Boss, &Health, &mut Ai, &mut Weapon), Changed<Health>>,
+) {
+    for (entity, mut boss, health, mut ai, mut weapon) in &mut boss_query {
+        let fraction = health.hp as f32 / health.max_hp as f32;
+        let mut changed = false;
+        while boss.phase + 1 < boss.phases.len()
+            && fraction <= boss.phases[boss.phase + 1].hp_fraction
+        {
+            boss.phase += 1;
+            changed = true;
+        }
+        if changed {
+            let phase = &boss.phases[boss.phase];
+            *ai = Ai::new(phase.behavior.clone());
+            weapon.pattern = phase.pattern.clone();
+            weapon.aim = phase.aim;
+            weapon
+                .cooldown_timer
+                .set_duration(std::time::Duration::from_secs_f32(phase.cooldown));
+            weapon.cooldown_timer.reset();
+            commands.entity(entity).insert(Shield {
+                capacity: Some(BOSS_PHASE_SHIELD),
+                timer: Timer::from_seconds(BOSS_PHASE_SHIELD_SECS, TimerMode::Once),
+            });
+        }
+    }
+}
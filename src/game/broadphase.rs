@@ -0,0 +1,55 @@
+use super::constants::COLLISION_CELL_SIZE;
+use bevy::{prelude::*, utils::HashMap};
+
+// Uniform grid over the world, so only boxes sharing a cell get tested against each other.
+// Rebuilt every collision step, keeping its allocations in between.
+#[derive(Default)]
+pub struct CollisionGrid {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    candidates: Vec<usize>,
+}
+
+impl CollisionGrid {
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.candidates.clear();
+    }
+
+    // Registers the box between `min` and `max` under `index`, in every cell it overlaps
+    pub fn insert(&mut self, index: usize, min: Vec2, max: Vec2) {
+        let (min, max) = cell_range(min, max);
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    // Every index sharing a cell with the box, each once and in ascending order
+    pub fn query(&mut self, min: Vec2, max: Vec2) -> &[usize] {
+        self.candidates.clear();
+        let (min, max) = cell_range(min, max);
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    self.candidates.extend_from_slice(cell);
+                }
+            }
+        }
+        self.candidates.sort_unstable();
+        self.candidates.dedup();
+        return &self.candidates;
+    }
+}
+
+// Cells covered by the box, inclusive on both ends
+fn cell_range(min: Vec2, max: Vec2) -> ((i32, i32), (i32, i32)) {
+    let min = min / COLLISION_CELL_SIZE;
+    let max = max / COLLISION_CELL_SIZE;
+    return (
+        (min.x.floor() as i32, min.y.floor() as i32),
+        (max.x.floor() as i32, max.y.floor() as i32),
+    );
+}
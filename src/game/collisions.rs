@@ -1,118 +1,212 @@
-use super::super::AppState;
+use super::broadphase::CollisionGrid;
 use super::components::*;
-use super::constants::TIME_STEP;
+use super::constants::ALLY_HITMASK;
 use super::events::*;
-use bevy::{prelude::*, sprite::collide_aabb::collide, time::*};
-use std::cmp::max;
-
-#[derive(Default)]
-pub struct CollisionEvent;
+use super::resources::Broadphase;
+use super::simulation::{SimulationApp, SimulationStep};
+use bevy::prelude::*;
 
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<WeaponFiredEvent>()
-            .add_event::<CollisionEvent>()
-            .add_event::<PlayerDeathEvent>()
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame)
-                    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64)) //FIXME: can potentially break WASM builds
-                    .with_system(check_collisions),
+        app.add_simulation_event::<WeaponFiredEvent>()
+            .add_simulation_event::<CollisionEvent>()
+            .add_simulation_event::<DamageEvent>()
+            .init_resource::<Broadphase>()
+            .add_simulation_system_set(
+                SimulationStep::Collisions,
+                SystemSet::new()
+                    .with_system(check_collisions)
+                    .with_system(collision_damage.after(check_collisions))
+                    .with_system(despawn_spent_bullets.after(check_collisions)),
             );
     }
 }
 
+// Finds every hit this step and reports it, what the hit does is left to the systems below
 pub fn check_collisions(
-    mut commands: Commands,
-    mut audio_event: EventWriter<AudioEvent>,
+    broadphase: Res<Broadphase>,
+    mut grid: Local<CollisionGrid>,
     mut collision_event: EventWriter<CollisionEvent>,
-    mut camera_shake_event: EventWriter<CameraShakeEvent>,
-    mut explosion_event: EventWriter<ExplosionEvent>,
-    mut player_death_event: EventWriter<PlayerDeathEvent>,
-    mut score_event: EventWriter<ScoreEvent>,
-    a_query: Query<(Entity, &Transform, &Collider, Option<&Bullet>)>,
-    mut b_query: Query<
-        (
-            Entity,
-            &mut Health,
-            &Transform,
-            &Collider,
-            Option<&DeathPointsAwarded>,
-            Option<&CameraShakeOnDeath>,
-            Option<&Player>,
-        ),
-        With<Actor>,
+    a_query: Query<(Entity, &Transform, &Collider, Option<&Invulnerable>), Without<PowerUp>>,
+    // Only actors with health can be hit, bullets and power-ups pass through each other
+    b_query: Query<
+        (Entity, &Transform, &Collider, Option<&Invulnerable>),
+        (With<Actor>, With<Health>),
     >,
 ) {
-    // TODO: Use quadtrees for more efficient collision resolution
-    // TODO: Find a way to break up this giant function
-    for (a_entity, a_transform, a_collider, a_bullet) in &a_query {
-        for (
-            b_entity,
-            mut b_health,
-            b_transform,
-            b_collider,
-            b_death_points,
-            b_camera_shake,
-            b_player,
-        ) in &mut b_query
-        {
-            // Skip self-collisions and identical hitmasks
-            if a_entity.id() == b_entity.id() || (a_collider.hitmask ^ b_collider.hitmask) == 0 {
+    // Snapshot of the actors that can be hit, candidates are visited in this
+    // order so that both broadphases report hits in the same sequence
+    let targets: Vec<(Entity, (Vec2, Vec2))> = b_query
+        .iter()
+        .map(|(entity, transform, collider, _)| (entity, collider.bounds(transform)))
+        .collect();
+    let everything: Vec<usize> = (0..targets.len()).collect();
+    grid.clear();
+    if *broadphase == Broadphase::Grid {
+        for (index, (_, (min, max))) in targets.iter().enumerate() {
+            grid.insert(index, *min, *max);
+        }
+    }
+    for (a_entity, a_transform, a_collider, a_invulnerable) in &a_query {
+        let a_bounds = a_collider.bounds(a_transform);
+        let candidates = match *broadphase {
+            Broadphase::BruteForce => &everything[..],
+            Broadphase::Grid => grid.query(a_bounds.0, a_bounds.1),
+        };
+        for index in candidates {
+            let (b_entity, b_bounds) = targets[*index];
+            let (_, b_transform, b_collider, b_invulnerable) = match b_query.get(b_entity) {
+                Ok(b) => b,
+                Err(_) => continue,
+            };
+            // Skip self-collisions and actors on the same side
+            if a_entity == b_entity || !a_collider.collides_with(b_collider) {
+                continue;
+            }
+            // Invulnerable actors pass through everything
+            if a_invulnerable.is_some() || b_invulnerable.is_some() {
                 continue;
             }
+            if a_collider.intersects(a_transform, b_collider, b_transform) {
+                collision_event.send(CollisionEvent {
+                    a: a_entity,
+                    b: b_entity,
+                    damage: a_collider.damage,
+                    kind: a_collider.damage_kind,
+                    hitmask: a_collider.hitmask,
+                    // Middle of where the two bounding boxes overlap
+                    point: 0.5 * (a_bounds.0.max(b_bounds.0) + a_bounds.1.min(b_bounds.1)),
+                });
+            }
+        }
+    }
+}
 
-            let collision = collide(
-                a_transform.translation,
-                a_collider.rect,
-                b_transform.translation,
-                b_collider.rect,
-            );
-            if collision.is_some() {
-                if let Some(_) = a_bullet {
-                    // If a is a bullet, despawn it on impact
-                    commands.entity(a_entity).despawn_recursive();
-                }
-                b_health.hp = max(b_health.hp - a_collider.damage, 0);
+// Everything with health takes the damage of whatever hits it
+pub fn collision_damage(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut damage_event: EventWriter<DamageEvent>,
+    query: Query<(), With<Health>>,
+) {
+    for collision in collision_events.iter() {
+        if collision.damage <= 0 || !query.contains(collision.b) {
+            continue;
+        }
+        damage_event.send(DamageEvent {
+            target: collision.b,
+            amount: collision.damage,
+            kind: collision.kind,
+            // Only the player's side scores, not asteroids or enemy fire
+            scored: collision.hitmask == ALLY_HITMASK,
+        });
+    }
+}
 
-                // Play damage sound
-                if a_collider.damage > 0 {
-                    audio_event.send(AudioEvent {
-                        clip: b_health.damage_sound.clone(),
-                    });
-                }
+// Bullets are used up by whatever they hit first
+fn despawn_spent_bullets(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    bullet_query: Query<(), With<Bullet>>,
+) {
+    for collision in collision_events.iter() {
+        if bullet_query.contains(collision.a) {
+            commands.entity(collision.a).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::constants::ENEMY_HITMASK;
+    use super::*;
+
+    fn collision_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Broadphase>()
+            .add_event::<CollisionEvent>()
+            .add_system(check_collisions);
+        return app;
+    }
+
+    fn bullet(app: &mut App, hitmask: u8) -> Entity {
+        return app
+            .world
+            .spawn((
+                Actor,
+                Bullet,
+                Transform::default(),
+                Collider {
+                    hitmask: hitmask,
+                    ..default()
+                },
+            ))
+            .id();
+    }
 
-                if b_health.hp == 0 {
-                    if let Some(d) = b_death_points {
-                        score_event.send(ScoreEvent {
-                            increment: d.points,
-                        });
-                    }
-                    if let Some(s) = b_camera_shake {
-                        camera_shake_event.send(CameraShakeEvent {
-                            magnitude: s.magnitude,
-                            duration_secs: s.duration_secs,
-                        });
-                        explosion_event.send(ExplosionEvent {
-                            position: b_transform.translation,
-                            lifetime: 0.25,
-                        });
-                    }
-                    if let Some(_) = b_player {
-                        player_death_event.send(PlayerDeathEvent::default())
-                    }
+    fn hits(app: &App) -> usize {
+        return app.world.resource::<Events<CollisionEvent>>().len();
+    }
 
-                    // Play death sound
-                    audio_event.send(AudioEvent {
-                        clip: b_health.death_sound.clone(),
-                    });
+    #[test]
+    fn bullets_pass_through_each_other() {
+        let mut app = collision_app();
+        bullet(&mut app, ALLY_HITMASK);
+        bullet(&mut app, ENEMY_HITMASK);
+        app.update();
+        assert_eq!(hits(&app), 0);
+    }
 
-                    commands.entity(b_entity).despawn_recursive();
-                }
+    #[test]
+    fn power_ups_do_not_absorb_bullets() {
+        let mut app = collision_app();
+        bullet(&mut app, ENEMY_HITMASK);
+        app.world.spawn((
+            Actor,
+            PowerUp {
+                effect: PowerUpEffect::WeaponUpgrade,
+                pickup_sound: Handle::default(),
+            },
+            Transform::default(),
+            Collider {
+                hitmask: ALLY_HITMASK,
+                ..default()
+            },
+        ));
+        app.update();
+        assert_eq!(hits(&app), 0);
+    }
 
-                collision_event.send_default();
-            }
-        }
+    #[test]
+    fn bullets_hit_actors_with_health() {
+        let mut app = collision_app();
+        let bullet = bullet(&mut app, ENEMY_HITMASK);
+        let target = app
+            .world
+            .spawn((
+                Actor,
+                Transform::default(),
+                Collider {
+                    hitmask: ALLY_HITMASK,
+                    ..default()
+                },
+                Health {
+                    hp: 1,
+                    max_hp: 1,
+                    death_sound: Handle::default(),
+                    damage_sound: Handle::default(),
+                },
+            ))
+            .id();
+        app.update();
+        let events = app.world.resource::<Events<CollisionEvent>>();
+        let mut reader = events.get_reader();
+        let hits: Vec<(Entity, Entity)> = reader
+            .iter(events)
+            .map(|collision| (collision.a, collision.b))
+            .collect();
+        assert_eq!(hits, vec![(bullet, target)]);
     }
 }
@@ -1,7 +1,12 @@
-use bevy::{prelude::*, time::Timer};
+use bevy::{prelude::*, time::Timer, utils::Duration};
+use std::sync::Arc;
 
 use super::actor::bullet::*;
-use super::ai::AiMode;
+use super::ai::AiBehavior;
+use super::constants::{
+    ACTOR_ACCELERATION_STEPS, ACTOR_DRAG, ASSET_SCALE, BLINK_INTERVAL, INVULNERABILITY_SECS,
+    MIN_WEAPON_COOLDOWN,
+};
 use super::levels::LevelSpawnInfo;
 use super::ENEMY_HITMASK;
 
@@ -12,42 +17,242 @@ pub struct Player;
 pub struct Enemy;
 
 #[derive(Component, Clone, Default)]
-pub struct Actor {
-    pub speed: Vec2,
+pub struct Actor;
+
+// How an actor moves, in units per simulation step. Controllers only set the
+// thrust, integrate_kinematics does the moving.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Kinematics {
+    pub velocity: Vec2,
+    pub max_speed: f32,    // thrust alone never goes faster, impulses can
+    pub acceleration: f32, // at full thrust
+    pub drag: f32,         // fraction of the velocity lost every step
+    pub thrust: Vec2,      // direction and strength up to 1, cleared every step
+}
+
+impl Kinematics {
+    // Gets up to max_speed from a standstill in ACTOR_ACCELERATION_STEPS
+    pub fn new(max_speed: f32) -> Self {
+        return Kinematics {
+            max_speed: max_speed,
+            acceleration: max_speed / ACTOR_ACCELERATION_STEPS,
+            drag: ACTOR_DRAG,
+            ..default()
+        };
+    }
+
+    // Coasts along at the same velocity until something pushes it
+    pub fn drifting(velocity: Vec2) -> Self {
+        return Kinematics {
+            velocity: velocity,
+            max_speed: velocity.length(),
+            ..default()
+        };
+    }
+
+    // Moved along by something else, pushes are lost straight away
+    pub fn anchored() -> Self {
+        return Kinematics {
+            drag: 1.0,
+            ..default()
+        };
+    }
+
+    pub fn with_acceleration(mut self, acceleration: f32) -> Self {
+        self.acceleration = acceleration;
+        return self;
+    }
+
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        return self;
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec2) -> Self {
+        self.velocity = velocity;
+        return self;
+    }
+
+    // Thrust that brings the velocity to `desired` as quickly as the actor can manage
+    pub fn steer(&mut self, desired: Vec2) {
+        if self.acceleration <= 0.0 {
+            return;
+        }
+        let needed = desired.clamp_length_max(self.max_speed) - self.velocity * (1.0 - self.drag);
+        self.thrust = (needed / self.acceleration).clamp_length_max(1.0);
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.velocity += impulse;
+    }
+}
+
+// Translation and rotation at the end of the last two simulation steps,
+// rendered frames show a blend of the two
+#[derive(Component, Clone, Copy)]
+pub struct Interpolated {
+    pub previous: (Vec3, Quat),
+    pub current: (Vec3, Quat),
 }
 
 #[derive(Component, Clone)]
 pub struct Health {
     pub hp: i32,
+    pub max_hp: i32,
     pub death_sound: Handle<AudioSource>,
     pub damage_sound: Handle<AudioSource>,
 }
 #[derive(Component, Clone, Default)]
 pub struct Bullet;
 
+// Sizes are in model units and get scaled along with the Transform
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColliderShape {
+    // Box that keeps lined up with the screen however the actor turns
+    Aabb(Vec2),
+    Circle(f32), // radius
+    // Box that turns with the actor
+    Obb(Vec2),
+    // Several shapes, each offset from the actor's center
+    Compound(Vec<(Vec2, ColliderShape)>),
+}
+
 #[derive(Component, Clone)]
 pub struct Collider {
-    pub rect: Vec2,
+    pub shape: ColliderShape,
     pub damage: i32,
+    pub damage_kind: DamageKind,
     pub hitmask: u8,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DamageKind {
+    #[default]
+    Kinetic, // Rams and debris
+    Energy,    // Lasers
+    Explosive, // Missiles and bombs
+}
+
+// Fraction of each kind of damage that is shrugged off, between 0 and 1
+#[derive(Component, Clone, Copy, Default)]
+pub struct Armor {
+    pub kinetic: f32,
+    pub energy: f32,
+    pub explosive: f32,
+    pub carried: f32, // damage let through that doesn't add up to a whole point yet
+}
+
+impl Armor {
+    // Fractions of a point carry over to the next hit, so even 1 damage hits
+    // are cut down by exactly the resistance over time
+    pub fn reduce(&mut self, amount: i32, kind: DamageKind) -> i32 {
+        let resistance = match kind {
+            DamageKind::Kinetic => self.kinetic,
+            DamageKind::Energy => self.energy,
+            DamageKind::Explosive => self.explosive,
+        };
+        let through = amount as f32 * (1.0 - resistance.clamp(0.0, 1.0)) + self.carried;
+        let whole = through.floor();
+        self.carried = through - whole;
+        return whole as i32;
+    }
+}
+
+// Tints the model for a moment after taking damage
+#[derive(Component)]
+pub struct HitFlash {
+    pub timer: Timer,
+}
+
+// Material a mesh of a flashing model wore before, put back when the flash ends
+#[derive(Component)]
+pub struct FlashedMaterial(pub Handle<StandardMaterial>);
+
+impl Collider {
+    // Each hitmask is a side: actors on the same side pass through each other,
+    // while hazards are a side of their own and so hit both allies and enemies
+    pub fn collides_with(&self, other: &Collider) -> bool {
+        return self.hitmask != other.hitmask;
+    }
+}
+
 impl Default for Collider {
     fn default() -> Self {
         return Collider {
-            rect: Vec2::new(3.0, 3.0),
+            shape: ColliderShape::Aabb(Vec2::new(3.0, 3.0) / ASSET_SCALE),
             damage: 0,
+            damage_kind: DamageKind::Kinetic,
             hitmask: ENEMY_HITMASK,
         };
     }
 }
 
+// Describes every projectile a weapon emits each time its cooldown fires
+#[derive(Clone)]
+pub struct FiringPattern {
+    pub barrels: Vec<Vec2>, // Offsets of each barrel from the firer
+    pub projectiles: u32,   // Projectiles per barrel, spread evenly across the cone
+    pub cone_angle: f32,    // Total spread in radians
+    pub burst_count: u32,   // Volleys fired per cooldown
+    pub burst_delay: f32,   // Seconds between volleys of a burst
+}
+
+impl FiringPattern {
+    pub fn single(offset: Vec2) -> Self {
+        return FiringPattern {
+            barrels: vec![offset],
+            projectiles: 1,
+            cone_angle: 0.0,
+            burst_count: 1,
+            burst_delay: 0.0,
+        };
+    }
+
+    pub fn cone(offset: Vec2, projectiles: u32, cone_angle: f32) -> Self {
+        let mut pattern = FiringPattern::single(offset);
+        pattern.projectiles = projectiles;
+        pattern.cone_angle = cone_angle;
+        return pattern;
+    }
+
+    // Angle of each projectile relative to the firing direction
+    pub fn angles(&self) -> Vec<f32> {
+        if self.projectiles <= 1 {
+            return vec![0.0];
+        }
+        let step = self.cone_angle / (self.projectiles - 1) as f32;
+        return (0..self.projectiles)
+            .map(|i| -0.5 * self.cone_angle + step * i as f32)
+            .collect();
+    }
+}
+
+// Which way a weapon points each volley
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AimMode {
+    // Along the firer's own facing
+    #[default]
+    Forward,
+    // At the player's current position
+    Aimed,
+    // At where the player will be when the shot arrives, given their current velocity
+    Predictive,
+    // Turns a further `step` radians after every volley
+    Spiral {
+        step: f32,
+    },
+}
+
 #[derive(Component, Clone)]
 pub struct Weapon {
     pub bullet_type: BulletType,
-    pub offset: Vec2,
+    pub pattern: FiringPattern,
+    pub aim: AimMode,
+    pub spiral_angle: f32, // Current offset of AimMode::Spiral
     pub firing_audio_clip: Handle<AudioSource>,
     pub cooldown_timer: Timer,
+    pub burst_timer: Timer,
+    pub burst_remaining: u32,
 }
 
 impl Weapon {
@@ -60,15 +265,149 @@ impl Weapon {
         let cooldown_timer = Timer::from_seconds(cooldown, TimerMode::Repeating);
         return Self {
             bullet_type: bullet_type,
-            offset: offset,
+            pattern: FiringPattern::single(offset),
+            aim: AimMode::Forward,
+            spiral_angle: 0.0,
             firing_audio_clip: firing_audio_clip,
             cooldown_timer: cooldown_timer,
+            burst_timer: Timer::default(),
+            burst_remaining: 0,
         };
     }
+
+    // Called when the cooldown fires, the first volley of the burst goes out right away
+    pub fn start_burst(&mut self) {
+        self.burst_remaining = self.pattern.burst_count.saturating_sub(1);
+        self.burst_timer = Timer::from_seconds(self.pattern.burst_delay, TimerMode::Repeating);
+    }
+
+    // Returns true when the next volley of the current burst is due
+    pub fn tick_burst(&mut self, delta: Duration) -> bool {
+        if self.burst_remaining == 0 {
+            return false;
+        }
+        self.burst_timer.tick(delta);
+        if self.burst_timer.just_finished() {
+            self.burst_remaining -= 1;
+            return true;
+        }
+        return false;
+    }
+
+    // Widens the spread to a 3x then 6x cone shot,
+    // after which the cooldown shortens down to MIN_WEAPON_COOLDOWN
+    pub fn upgrade(&mut self) {
+        if self.pattern.projectiles < 3 {
+            self.pattern.projectiles = 3;
+            self.pattern.cone_angle = 20.0_f32.to_radians();
+        } else if self.pattern.projectiles < 6 {
+            self.pattern.projectiles = 6;
+            self.pattern.cone_angle = 45.0_f32.to_radians();
+        } else {
+            let cooldown = self.cooldown_timer.duration().as_secs_f32() * 0.8;
+            self.cooldown_timer
+                .set_duration(Duration::from_secs_f32(cooldown.max(MIN_WEAPON_COOLDOWN)));
+        }
+    }
 }
 
 #[derive(Component, Clone)]
 pub struct AutoFire;
+
+// Collisions involving this actor are ignored
+#[derive(Component, Clone, Default)]
+pub struct Invulnerable;
+
+// Temporary invulnerability after respawning, the model blinks until it runs out
+#[derive(Component, Clone)]
+pub struct RespawnInvulnerability {
+    pub timer: Timer,
+    pub blink_timer: Timer,
+}
+
+impl Default for RespawnInvulnerability {
+    fn default() -> Self {
+        return RespawnInvulnerability {
+            timer: Timer::from_seconds(INVULNERABILITY_SECS, TimerMode::Once),
+            blink_timer: Timer::from_seconds(BLINK_INTERVAL, TimerMode::Repeating),
+        };
+    }
+}
+
+// Screen-clearing secondary weapon
+#[derive(Component, Clone)]
+pub struct Bomb {
+    pub charges: u32,
+}
+
+// Turns towards the nearest actor with a different hitmask
+#[derive(Component, Clone)]
+pub struct Homing {
+    pub turn_rate: f32, // radians per second
+}
+
+// Multi-part boss, the core stays Invulnerable while any of its turrets are alive
+#[derive(Component, Clone)]
+pub struct Boss {
+    pub phases: Vec<BossPhase>,
+    pub phase: usize,
+}
+
+#[derive(Clone)]
+pub struct BossPhase {
+    pub hp_fraction: f32, // Starts once the core's hp drops to this fraction of max_hp
+    pub behavior: AiBehavior,
+    pub pattern: FiringPattern,
+    pub aim: AimMode,
+    pub cooldown: f32,
+}
+
+// Turret mounted on another actor, follows its anchor around and aims at the player
+#[derive(Component, Clone)]
+pub struct Turret {
+    pub anchor: Entity,
+    pub offset: Vec2,
+    pub turn_rate: f32, // radians per second
+}
+
+// Turret base model, keeps its orientation while the barrel above it turns
+#[derive(Component, Clone, Default)]
+pub struct TurretBase;
+
+// Drifting hazard, anything bigger than size 1 splits in two when destroyed
+#[derive(Component, Clone)]
+pub struct Asteroid {
+    pub size: u32,
+}
+
+// Constant velocity (per simulation step) and tumble, independent of the actor's rotation
+#[derive(Component, Clone, Copy)]
+pub struct Drift {
+    pub velocity: Vec2, // what the asteroid starts out with
+    pub spin_axis: Vec3,
+    pub spin: f32, // radians per second
+}
+
+#[derive(Clone, Copy)]
+pub enum PowerUpEffect {
+    WeaponUpgrade,
+    Heal(i32),
+    Shield { duration_secs: f32 },
+    ScoreMultiplier { factor: i32, duration_secs: f32 },
+}
+
+#[derive(Component, Clone)]
+pub struct PowerUp {
+    pub effect: PowerUpEffect,
+    pub pickup_sound: Handle<AudioSource>,
+}
+
+// Soaks up incoming damage until the timer runs out
+#[derive(Component, Clone)]
+pub struct Shield {
+    pub capacity: Option<i32>, // Damage left to absorb before it breaks, unlimited when None
+    pub timer: Timer,
+}
 #[derive(Component)]
 pub struct Wall;
 
@@ -78,6 +417,8 @@ pub struct AiActorSpawner {
     pub spawn_infos: Vec<LevelSpawnInfo>,
     pub ttl_timer: Timer,       // init from spawn_infos
     pub frequency_timer: Timer, //init from spawn_infos
+    pub looping: bool,
+    pub boss_spawned: bool, // Set once a segment ending on BossDefeated has spawned its boss
 }
 
 impl AiActorSpawner {
@@ -87,14 +428,61 @@ impl AiActorSpawner {
             ttl_timer: Timer::from_seconds(spawn_infos[0].ttl, TimerMode::Once),
             frequency_timer: Timer::from_seconds(spawn_infos[0].frequency, TimerMode::Repeating),
             spawn_infos: spawn_infos,
+            looping: false,
+            boss_spawned: false,
         };
     }
 }
 
-#[derive(Component, Clone, Default)]
+// Member of a formation wave, keeps its offset from the leader instead of running its own Ai.
+// Falls back to its own Ai once the leader is gone.
+#[derive(Component, Clone)]
+pub struct FormationSlot {
+    pub leader: Entity,
+    pub offset: Vec2,
+}
+
+// Flies through the points in order at the actor's max speed, then goes back to its own Ai
+#[derive(Component, Clone)]
+pub struct FollowPath {
+    pub points: Arc<Vec<Vec2>>,
+    pub index: usize,
+}
+
+#[derive(Component, Clone)]
 pub struct Ai {
-    pub mode: AiMode,
-    pub timer: Timer,
+    pub behavior: AiBehavior,
+    pub segment: usize,        // Index of the running segment in behavior.segments
+    pub timer: Timer,          // Finishes once the running segment's duration is up
+    pub strafe_direction: f32, // Vertical direction of Movement::Strafe
+}
+
+impl Default for Ai {
+    fn default() -> Self {
+        return Ai::new(AiBehavior::default());
+    }
+}
+
+impl Ai {
+    pub fn new(behavior: AiBehavior) -> Self {
+        let mut ai = Ai {
+            behavior: behavior,
+            segment: 0,
+            timer: Timer::default(),
+            strafe_direction: 1.0,
+        };
+        ai.enter(0);
+        return ai;
+    }
+
+    // Switches to another segment and restarts the segment timer
+    pub fn enter(&mut self, index: usize) {
+        self.segment = index;
+        self.timer = match self.behavior.segments[index].duration {
+            Some(duration) => Timer::from_seconds(duration, TimerMode::Once),
+            None => Timer::default(),
+        };
+    }
 }
 
 #[derive(Component, Clone, Default)]
@@ -120,6 +508,34 @@ impl Default for TimedOobDespawn {
 #[derive(Component)]
 pub struct PlayerScoreBoard;
 
+#[derive(Component)]
+pub struct PlayerBombCounter;
+
+#[derive(Component)]
+pub struct PlayerLivesCounter;
+
+// Fill of the player's health bar
+#[derive(Component)]
+pub struct PlayerHealthBar {
+    pub last_hp: i32,
+    pub flash_timer: Timer,
+}
+
+impl Default for PlayerHealthBar {
+    fn default() -> Self {
+        let mut flash_timer = Timer::from_seconds(0.15, TimerMode::Once);
+        flash_timer.tick(flash_timer.duration()); // Not flashing until damaged
+        return PlayerHealthBar {
+            last_hp: 0,
+            flash_timer: flash_timer,
+        };
+    }
+}
+
+// Everything belonging to the health bar, for despawning
+#[derive(Component)]
+pub struct PlayerHealthBarRoot;
+
 #[derive(Component, Clone)]
 pub struct DeathPointsAwarded {
     pub points: i32,
@@ -153,3 +569,70 @@ impl Default for CameraShakeOnDeath {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(armor: &mut Armor, count: usize, amount: i32, kind: DamageKind) -> Vec<i32> {
+        return (0..count).map(|_| armor.reduce(amount, kind)).collect();
+    }
+
+    #[test]
+    fn no_armor_lets_everything_through() {
+        let mut armor = Armor::default();
+        assert_eq!(armor.reduce(7, DamageKind::Energy), 7);
+        assert_eq!(armor.reduce(1, DamageKind::Kinetic), 1);
+    }
+
+    #[test]
+    fn full_resistance_stops_everything() {
+        let mut armor = Armor {
+            kinetic: 1.0,
+            ..default()
+        };
+        assert_eq!(hits(&mut armor, 10, 5, DamageKind::Kinetic), vec![0; 10]);
+    }
+
+    #[test]
+    fn small_hits_add_up_to_the_resisted_amount() {
+        let mut armor = Armor {
+            explosive: 0.5,
+            ..default()
+        };
+        assert_eq!(
+            hits(&mut armor, 4, 1, DamageKind::Explosive),
+            vec![0, 1, 0, 1]
+        );
+
+        let mut armor = Armor {
+            energy: 0.25,
+            ..default()
+        };
+        assert_eq!(hits(&mut armor, 4, 1, DamageKind::Energy), vec![0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn each_kind_uses_its_own_resistance() {
+        let mut armor = Armor {
+            kinetic: 1.0,
+            energy: 0.0,
+            explosive: 0.5,
+            ..default()
+        };
+        assert_eq!(armor.reduce(4, DamageKind::Kinetic), 0);
+        assert_eq!(armor.reduce(4, DamageKind::Energy), 4);
+        assert_eq!(armor.reduce(4, DamageKind::Explosive), 2);
+    }
+
+    #[test]
+    fn resistance_is_clamped() {
+        let mut armor = Armor {
+            kinetic: 1.5,
+            energy: -1.0,
+            ..default()
+        };
+        assert_eq!(armor.reduce(3, DamageKind::Kinetic), 0);
+        assert_eq!(armor.reduce(3, DamageKind::Energy), 3);
+    }
+}
@@ -2,9 +2,69 @@ use bevy::prelude::*;
 
 // PLAYER
 pub const PLAYER_SPAWN_POS: Vec2 = Vec2::new(-150.0, 0.0);
+pub const PLAYER_LIVES: u32 = 3;
+pub const RESPAWN_DELAY: f32 = 1.5;
+pub const INVULNERABILITY_SECS: f32 = 2.5;
+pub const BLINK_INTERVAL: f32 = 0.1;
+
+// CONTROLS
+pub const BINDINGS_PATH: &str = "bindings.ron"; // created with the defaults on first launch
 
 // TIME
 pub const TIME_STEP: f32 = 1.0 / 60.0;
+pub const MAX_STEPS_PER_FRAME: u32 = 5; // the rest of a long frame is skipped
+pub const FAST_FORWARD_SPEED: u32 = 8;
+
+// KINEMATICS
+pub const ACTOR_ACCELERATION_STEPS: f32 = 8.0; // from a standstill to max speed
+pub const ACTOR_DRAG: f32 = 0.05;
+pub const HIT_KNOCKBACK: f32 = 1.5; // per point of damage
+pub const DEATH_BLAST_RADIUS: f32 = 120.0;
+pub const DEATH_BLAST_IMPULSE: f32 = 3.0; // at the center, falls off to nothing at the radius
+pub const BOMB_BLAST_RADIUS: f32 = 400.0;
+pub const BOMB_BLAST_IMPULSE: f32 = 12.0;
+pub const FORMATION_STIFFNESS: f32 = 0.2; // fraction of the distance to its slot closed per step
+
+// RNG
+pub const COSMETIC_RNG_SALT: u64 = 0x9E37_79B9_7F4A_7C15; // keeps the cosmetic stream apart from gameplay
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// REPLAYS
+pub const REPLAY_DIR: &str = "replays"; // every run is recorded here
+pub const REPLAY_VERSION: u32 = 1; // bumped whenever a change to the game breaks old replays
+pub const FAST_FORWARD_KEY: KeyCode = KeyCode::F;
+
+// HEALTH
+pub const HIT_FLASH_SECS: f32 = 0.1;
+pub const HIT_FLASH_COLOR: Color = Color::rgb(1.0, 0.35, 0.35); // emissive tint
+pub const BOSS_PHASE_SHIELD: i32 = 10; // absorbed when the boss changes phase
+pub const BOSS_PHASE_SHIELD_SECS: f32 = 3.0;
+
+// COLLISIONS
+pub const COLLISION_CELL_SIZE: f32 = 64.0; // a little larger than most colliders
+
+// WEAPONS
+pub const MIN_WEAPON_COOLDOWN: f32 = 0.05;
+pub const MISSILE_TURN_RATE: f32 = 2.5; // radians per second
+pub const MISSILE_LIFETIME: f32 = 6.0;
+pub const BOMB_CHARGES: u32 = 3;
+pub const BOMB_DAMAGE: i32 = 10;
+
+// TURRETS
+pub const PLATFORM_TURRET_OFFSETS: [Vec2; 2] = [Vec2::new(-60.0, 0.0), Vec2::new(60.0, 0.0)];
+
+// BOSS
+pub const BOSS_HOVER_X: f32 = RIGHT_WALL - 150.0;
+pub const BOSS_TURRET_OFFSETS: [Vec2; 3] = [
+    Vec2::new(0.0, 90.0),
+    Vec2::new(0.0, -90.0),
+    Vec2::new(60.0, 0.0),
+];
+
+// ASTEROIDS
+pub const ASTEROID_MAX_SIZE: u32 = 3;
+pub const ASTEROID_HP: [i32; ASTEROID_MAX_SIZE as usize] = [1, 3, 6]; // by size
+pub const ASTEROID_FIELD_COUNT: u32 = 6;
 
 // Models
 pub const SCOREBOARD_FONT_SIZE: f32 = 20.0;
@@ -16,10 +76,23 @@ pub const UI_COLOR: Color = Color::Rgba {
     alpha: 1.0,
 };
 
+pub const HEALTH_BAR_WIDTH: f32 = 200.0;
+pub const HEALTH_BAR_HEIGHT: f32 = 14.0;
+pub const HEALTH_BAR_COLOR: Color = Color::rgb(0.2, 0.85, 0.35);
+pub const HEALTH_BAR_LOW_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
+pub const HEALTH_BAR_FLASH_COLOR: Color = Color::WHITE;
+pub const HEALTH_BAR_BACKGROUND_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+pub const LOW_HEALTH_FRACTION: f32 = 0.25;
+
 pub const ALLY_HITMASK: u8 = 1; // 0b0001
 pub const ENEMY_HITMASK: u8 = 2; // 0b0010
+pub const HAZARD_HITMASK: u8 = 4; // 0b0100, collides with both allies and enemies
 
 pub const ASSET_SCALE: f32 = 20.0;
+// Models drawn at another scale, their colliders are divided by the same one
+pub const ENEMY_SCALE: f32 = 23.0;
+pub const POWER_UP_SCALE: f32 = 23.0;
+pub const ASTEROID_SCALE: f32 = 12.0; // per size of the asteroid
 
 // Wall Constants
 pub const WALL_THICKNESS: f32 = 1.0 * ASSET_SCALE;
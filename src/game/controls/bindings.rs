@@ -0,0 +1,331 @@
+// Which keys, gamepad buttons and sticks trigger each action.
+// Inputs are stored by name so the table can be written to and read from RON.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::Action;
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ActionBinding {
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub buttons: Vec<String>,
+    #[serde(default)]
+    pub axes: Vec<AxisBinding>,
+}
+
+// One direction of an analog stick axis
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AxisBinding {
+    pub axis: String,
+    pub positive: bool,
+}
+
+#[derive(Resource, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct InputBindings {
+    pub deadzone: f32, // Stick travel ignored around the center, between 0 and 1
+    pub actions: BTreeMap<Action, ActionBinding>,
+}
+
+impl InputBindings {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let bindings: InputBindings = ron::de::from_str(&text).map_err(|e| e.to_string())?;
+        bindings.validate()?;
+        return Ok(bindings);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| e.to_string());
+    }
+
+    // Every name must be one this module knows about
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.deadzone) {
+            return Err(format!("deadzone {} is not between 0 and 1", self.deadzone));
+        }
+        for (action, binding) in &self.actions {
+            for key in &binding.keys {
+                if key_code(key).is_none() {
+                    return Err(format!("{:?}: unknown key {:?}", action, key));
+                }
+            }
+            for button in &binding.buttons {
+                if button_type(button).is_none() {
+                    return Err(format!("{:?}: unknown gamepad button {:?}", action, button));
+                }
+            }
+            for axis in &binding.axes {
+                if axis_type(&axis.axis).is_none() {
+                    return Err(format!(
+                        "{:?}: unknown gamepad axis {:?}",
+                        action, axis.axis
+                    ));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn binding(&self, action: Action) -> Option<&ActionBinding> {
+        return self.actions.get(&action);
+    }
+
+    // Straight from the devices, for the menus which run while the simulation is stopped.
+    // Sticks are left out, they have no edge to speak of.
+    pub fn just_pressed(
+        &self,
+        action: Action,
+        keyboard_input: &Input<KeyCode>,
+        gamepads: &Gamepads,
+        button_input: &Input<GamepadButton>,
+    ) -> bool {
+        let binding = match self.binding(action) {
+            Some(binding) => binding,
+            None => return false,
+        };
+        if binding
+            .keys
+            .iter()
+            .filter_map(|key| key_code(key))
+            .any(|key| keyboard_input.just_pressed(key))
+        {
+            return true;
+        }
+        return gamepads.iter().any(|gamepad| {
+            binding
+                .buttons
+                .iter()
+                .filter_map(|button| button_type(button))
+                .any(|button| button_input.just_pressed(GamepadButton::new(gamepad, button)))
+        });
+    }
+}
+
+impl Default for InputBindings {
+    // Arrows or WASD, Space to fire, and the usual twin-stick gamepad layout
+    fn default() -> Self {
+        let bind = |keys: &[&str], buttons: &[&str], axis: Option<(&str, bool)>| ActionBinding {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            axes: axis
+                .map(|(axis, positive)| AxisBinding {
+                    axis: axis.to_string(),
+                    positive: positive,
+                })
+                .into_iter()
+                .collect(),
+        };
+        let mut actions = BTreeMap::new();
+        actions.insert(
+            Action::MoveUp,
+            bind(&["Up", "W"], &["DPadUp"], Some(("LeftStickY", true))),
+        );
+        actions.insert(
+            Action::MoveDown,
+            bind(&["Down", "S"], &["DPadDown"], Some(("LeftStickY", false))),
+        );
+        actions.insert(
+            Action::MoveLeft,
+            bind(&["Left", "A"], &["DPadLeft"], Some(("LeftStickX", false))),
+        );
+        actions.insert(
+            Action::MoveRight,
+            bind(&["Right", "D"], &["DPadRight"], Some(("LeftStickX", true))),
+        );
+        actions.insert(
+            Action::Fire,
+            bind(&["Space", "J"], &["South", "RightTrigger2"], None),
+        );
+        actions.insert(
+            Action::Bomb,
+            bind(&["B", "K"], &["East", "LeftTrigger2"], None),
+        );
+        actions.insert(Action::Pause, bind(&["Escape", "P"], &["Start"], None));
+        return InputBindings {
+            deadzone: 0.2,
+            actions: actions,
+        };
+    }
+}
+
+const KEY_NAMES: [(&str, KeyCode); 48] = [
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("Key0", KeyCode::Key0),
+    ("Key1", KeyCode::Key1),
+    ("Key2", KeyCode::Key2),
+    ("Key3", KeyCode::Key3),
+    ("Key4", KeyCode::Key4),
+    ("Key5", KeyCode::Key5),
+    ("Key6", KeyCode::Key6),
+    ("Key7", KeyCode::Key7),
+    ("Key8", KeyCode::Key8),
+    ("Key9", KeyCode::Key9),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Space", KeyCode::Space),
+    ("Return", KeyCode::Return),
+    ("Escape", KeyCode::Escape),
+    ("Tab", KeyCode::Tab),
+    ("LShift", KeyCode::LShift),
+    ("RShift", KeyCode::RShift),
+    ("LControl", KeyCode::LControl),
+    ("RControl", KeyCode::RControl),
+];
+
+const BUTTON_NAMES: [(&str, GamepadButtonType); 17] = [
+    ("South", GamepadButtonType::South),
+    ("East", GamepadButtonType::East),
+    ("North", GamepadButtonType::North),
+    ("West", GamepadButtonType::West),
+    ("LeftTrigger", GamepadButtonType::LeftTrigger),
+    ("LeftTrigger2", GamepadButtonType::LeftTrigger2),
+    ("RightTrigger", GamepadButtonType::RightTrigger),
+    ("RightTrigger2", GamepadButtonType::RightTrigger2),
+    ("Select", GamepadButtonType::Select),
+    ("Start", GamepadButtonType::Start),
+    ("Mode", GamepadButtonType::Mode),
+    ("LeftThumb", GamepadButtonType::LeftThumb),
+    ("RightThumb", GamepadButtonType::RightThumb),
+    ("DPadUp", GamepadButtonType::DPadUp),
+    ("DPadDown", GamepadButtonType::DPadDown),
+    ("DPadLeft", GamepadButtonType::DPadLeft),
+    ("DPadRight", GamepadButtonType::DPadRight),
+];
+
+const AXIS_NAMES: [(&str, GamepadAxisType); 4] = [
+    ("LeftStickX", GamepadAxisType::LeftStickX),
+    ("LeftStickY", GamepadAxisType::LeftStickY),
+    ("RightStickX", GamepadAxisType::RightStickX),
+    ("RightStickY", GamepadAxisType::RightStickY),
+];
+
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    return KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| *key_name == name)
+        .map(|(_, key)| *key);
+}
+
+pub fn button_type(name: &str) -> Option<GamepadButtonType> {
+    return BUTTON_NAMES
+        .iter()
+        .find(|(button_name, _)| *button_name == name)
+        .map(|(_, button)| *button);
+}
+
+pub fn axis_type(name: &str) -> Option<GamepadAxisType> {
+    return AXIS_NAMES
+        .iter()
+        .find(|(axis_name, _)| *axis_name == name)
+        .map(|(_, axis)| *axis);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_survive_a_save_and_load() {
+        let mut bindings = InputBindings::default();
+        bindings.deadzone = 0.35;
+        bindings.actions.insert(
+            Action::Bomb,
+            ActionBinding {
+                keys: vec!["X".to_string()],
+                buttons: vec!["North".to_string()],
+                axes: vec![AxisBinding {
+                    axis: "RightStickY".to_string(),
+                    positive: false,
+                }],
+            },
+        );
+        let path = std::env::temp_dir().join(format!("bindings-{}.ron", std::process::id()));
+
+        bindings.save(&path).unwrap();
+        let loaded = InputBindings::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), bindings);
+    }
+
+    #[test]
+    fn default_bindings_are_valid() {
+        assert_eq!(InputBindings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        let mut bindings = InputBindings::default();
+        bindings.actions.get_mut(&Action::Fire).unwrap().keys = vec!["Spacebar".to_string()];
+        assert_eq!(
+            bindings.validate(),
+            Err("Fire: unknown key \"Spacebar\"".to_string())
+        );
+
+        let mut bindings = InputBindings::default();
+        bindings.actions.get_mut(&Action::Fire).unwrap().buttons = vec!["Triangle".to_string()];
+        assert_eq!(
+            bindings.validate(),
+            Err("Fire: unknown gamepad button \"Triangle\"".to_string())
+        );
+
+        let mut bindings = InputBindings::default();
+        bindings.actions.get_mut(&Action::MoveUp).unwrap().axes[0].axis = "Wheel".to_string();
+        assert_eq!(
+            bindings.validate(),
+            Err("MoveUp: unknown gamepad axis \"Wheel\"".to_string())
+        );
+    }
+
+    #[test]
+    fn deadzone_must_leave_some_travel() {
+        let mut bindings = InputBindings::default();
+        bindings.deadzone = 1.0;
+        assert!(bindings.validate().is_err());
+        bindings.deadzone = -0.1;
+        assert!(bindings.validate().is_err());
+    }
+
+    #[test]
+    fn broken_files_fail_to_load() {
+        let path = std::env::temp_dir().join(format!("broken-{}.ron", std::process::id()));
+        fs::write(&path, "(deadzone: 0.2, actions: {").unwrap();
+        let loaded = InputBindings::load(&path);
+        let _ = fs::remove_file(&path);
+        assert!(loaded.is_err());
+    }
+}
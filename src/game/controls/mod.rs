@@ -0,0 +1,351 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::super::AppState;
+use super::constants::BINDINGS_PATH;
+use super::simulation::{SimulationApp, SimulationStep};
+pub mod bindings;
+pub use bindings::*;
+
+// Gameplay reads these instead of raw keys, so every input device and binding works the same
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Bomb,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Bomb,
+        Action::Pause,
+    ];
+}
+
+// How strongly each action is held this step and the last, between 0 and 1.
+// Input sources only ever raise a value, so any of them can hold an action down.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    values: [f32; Action::ALL.len()],
+    previous: [f32; Action::ALL.len()],
+}
+
+impl ActionState {
+    // Starts a new step with nothing held
+    pub fn advance(&mut self) {
+        self.previous = self.values;
+        self.values = [0.0; Action::ALL.len()];
+    }
+
+    pub fn press(&mut self, action: Action, value: f32) {
+        let held = &mut self.values[action as usize];
+        *held = held.max(value.clamp(0.0, 1.0));
+    }
+
+    pub fn value(&self, action: Action) -> f32 {
+        return self.values[action as usize];
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        return self.values[action as usize] > 0.0;
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        return self.pressed(action) && self.previous[action as usize] == 0.0;
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        return !self.pressed(action) && self.previous[action as usize] > 0.0;
+    }
+
+    // Every action's value, in the order of Action::ALL
+    pub fn values(&self) -> [f32; Action::ALL.len()] {
+        return self.values;
+    }
+
+    // Replaces what the input sources wrote this step, the previous step is kept
+    pub fn set_values(&mut self, values: [f32; Action::ALL.len()]) {
+        self.values = values;
+    }
+
+    // Combined movement actions, each axis between -1 and 1
+    pub fn movement(&self) -> Vec2 {
+        return Vec2::new(
+            self.value(Action::MoveRight) - self.value(Action::MoveLeft),
+            self.value(Action::MoveUp) - self.value(Action::MoveDown),
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+pub enum ControlsSystem {
+    // Runs first so that every input source starts from a clean step
+    Advance,
+    // Every input source writes its actions here
+    Read,
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        // Sampled once per simulation step, so every step sees the input it was given
+        app.init_resource::<ActionState>()
+            .insert_resource(load_bindings())
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_actions))
+            .add_simulation_system(
+                SimulationStep::Input,
+                advance_actions.label(ControlsSystem::Advance),
+            )
+            .add_simulation_system(
+                SimulationStep::Input,
+                read_keyboard
+                    .label(ControlsSystem::Read)
+                    .after(ControlsSystem::Advance),
+            )
+            .add_simulation_system(
+                SimulationStep::Input,
+                read_gamepads
+                    .label(ControlsSystem::Read)
+                    .after(ControlsSystem::Advance),
+            );
+    }
+}
+
+// Bindings from the config file, which is written out with the defaults if missing or broken
+fn load_bindings() -> InputBindings {
+    let path = Path::new(BINDINGS_PATH);
+    match InputBindings::load(path) {
+        Ok(bindings) => return bindings,
+        Err(e) => {
+            warn!(
+                "Using default bindings, could not load {}: {}",
+                BINDINGS_PATH, e
+            );
+        }
+    }
+    let bindings = InputBindings::default();
+    if !path.exists() {
+        if let Err(e) = bindings.save(path) {
+            warn!("Could not save bindings to {}: {}", BINDINGS_PATH, e);
+        }
+    }
+    return bindings;
+}
+
+// Nothing carries over from the last run
+fn reset_actions(mut actions: ResMut<ActionState>) {
+    *actions = ActionState::default();
+}
+
+fn advance_actions(mut actions: ResMut<ActionState>) {
+    actions.advance();
+}
+
+fn read_keyboard(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut actions: ResMut<ActionState>,
+) {
+    for action in Action::ALL {
+        let binding = match bindings.binding(action) {
+            Some(binding) => binding,
+            None => continue,
+        };
+        if binding
+            .keys
+            .iter()
+            .filter_map(|key| key_code(key))
+            .any(|key| keyboard_input.pressed(key))
+        {
+            actions.press(action, 1.0);
+        }
+    }
+}
+
+fn read_gamepads(
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<InputBindings>,
+    mut actions: ResMut<ActionState>,
+) {
+    for gamepad in gamepads.iter() {
+        read_gamepad(gamepad, &button_input, &axes, &bindings, &mut actions);
+    }
+}
+
+fn read_gamepad(
+    gamepad: Gamepad,
+    button_input: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+    bindings: &InputBindings,
+    actions: &mut ActionState,
+) {
+    for action in Action::ALL {
+        let binding = match bindings.binding(action) {
+            Some(binding) => binding,
+            None => continue,
+        };
+        if binding
+            .buttons
+            .iter()
+            .filter_map(|button| button_type(button))
+            .any(|button| button_input.pressed(GamepadButton::new(gamepad, button)))
+        {
+            actions.press(action, 1.0);
+        }
+        for axis_binding in &binding.axes {
+            let axis = match axis_type(&axis_binding.axis) {
+                Some(axis) => axis,
+                None => continue,
+            };
+            let value = axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+            let value = if axis_binding.positive { value } else { -value };
+            // Rescale so that travel starts from zero just past the deadzone
+            if value > bindings.deadzone {
+                actions.press(
+                    action,
+                    (value - bindings.deadzone) / (1.0 - bindings.deadzone),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs one step of the keyboard input source
+    fn keyboard_step(world: &mut World) {
+        let mut stage = SystemStage::single_threaded();
+        stage
+            .add_system(advance_actions.label(ControlsSystem::Advance))
+            .add_system(read_keyboard.after(ControlsSystem::Advance));
+        stage.run(world);
+    }
+
+    fn keyboard_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Input::<KeyCode>::default());
+        world.insert_resource(InputBindings::default());
+        world.init_resource::<ActionState>();
+        return world;
+    }
+
+    #[test]
+    fn held_keys_are_pressed_until_released() {
+        let mut world = keyboard_world();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+        keyboard_step(&mut world);
+        {
+            let actions = world.resource::<ActionState>();
+            assert!(actions.pressed(Action::Fire));
+            assert!(actions.just_pressed(Action::Fire));
+            assert!(!actions.pressed(Action::Bomb));
+        }
+
+        // Still held on the next step, but no longer just pressed
+        keyboard_step(&mut world);
+        {
+            let actions = world.resource::<ActionState>();
+            assert!(actions.pressed(Action::Fire));
+            assert!(!actions.just_pressed(Action::Fire));
+        }
+
+        world
+            .resource_mut::<Input<KeyCode>>()
+            .release(KeyCode::Space);
+        keyboard_step(&mut world);
+        let actions = world.resource::<ActionState>();
+        assert!(!actions.pressed(Action::Fire));
+        assert!(actions.just_released(Action::Fire));
+    }
+
+    #[test]
+    fn keys_move_at_full_speed_and_opposites_cancel() {
+        let mut world = keyboard_world();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Up);
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::D);
+        keyboard_step(&mut world);
+        assert_eq!(
+            world.resource::<ActionState>().movement(),
+            Vec2::new(1.0, 1.0)
+        );
+
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Left);
+        keyboard_step(&mut world);
+        assert_eq!(
+            world.resource::<ActionState>().movement(),
+            Vec2::new(0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn gamepad_buttons_and_sticks_press_actions() {
+        let gamepad = Gamepad::new(0);
+        let bindings = InputBindings::default();
+        let mut button_input = Input::<GamepadButton>::default();
+        let mut axes = Axis::<GamepadAxis>::default();
+        button_input.press(GamepadButton::new(gamepad, GamepadButtonType::South));
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), -0.6);
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY), 1.0);
+
+        let mut actions = ActionState::default();
+        actions.advance();
+        read_gamepad(gamepad, &button_input, &axes, &bindings, &mut actions);
+
+        assert!(actions.just_pressed(Action::Fire));
+        // Travel past the deadzone of 0.2 is rescaled to the full range
+        assert!((actions.value(Action::MoveLeft) - 0.5).abs() < 1e-6);
+        assert_eq!(actions.value(Action::MoveUp), 1.0);
+        assert!(!actions.pressed(Action::MoveRight));
+        assert!(!actions.pressed(Action::MoveDown));
+        let movement = actions.movement();
+        assert!((movement - Vec2::new(-0.5, 1.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn sticks_inside_the_deadzone_do_nothing() {
+        let gamepad = Gamepad::new(0);
+        let bindings = InputBindings::default();
+        let button_input = Input::<GamepadButton>::default();
+        let mut axes = Axis::<GamepadAxis>::default();
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), 0.2);
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY), -0.1);
+
+        let mut actions = ActionState::default();
+        actions.advance();
+        read_gamepad(gamepad, &button_input, &axes, &bindings, &mut actions);
+
+        assert_eq!(actions.movement(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn keyboard_and_gamepad_hold_the_same_action() {
+        let gamepad = Gamepad::new(0);
+        let bindings = InputBindings::default();
+        let button_input = Input::<GamepadButton>::default();
+        let mut axes = Axis::<GamepadAxis>::default();
+        axes.set(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX), 0.6);
+
+        // The key holds it fully, the stick can't pull it back down
+        let mut actions = ActionState::default();
+        actions.advance();
+        actions.press(Action::MoveRight, 1.0);
+        read_gamepad(gamepad, &button_input, &axes, &bindings, &mut actions);
+
+        assert_eq!(actions.value(Action::MoveRight), 1.0);
+    }
+}
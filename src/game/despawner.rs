@@ -1,17 +1,19 @@
 use crate::utils::despawn_all;
-use bevy::{prelude::*, time::*};
+use bevy::prelude::*;
 
 use super::super::AppState;
 use super::components::*;
 use super::constants::*;
+use super::resources::SimulationTime;
+use super::simulation::{SimulationApp, SimulationStep};
 
 pub struct DespawnerPlugin;
 
 impl Plugin for DespawnerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(AppState::InGame)
-                //.with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
+        app.add_simulation_system_set(
+            SimulationStep::Despawn,
+            SystemSet::new()
                 .with_system(timed_oob_despawn)
                 .with_system(timed_despawn),
         )
@@ -24,7 +26,7 @@ impl Plugin for DespawnerPlugin {
 }
 
 fn timed_despawn(
-    time: Res<Time>,
+    time: Res<SimulationTime>,
     mut commands: Commands,
     mut query: Query<(Entity, &mut TimedDespawn)>,
 ) {
@@ -37,7 +39,7 @@ fn timed_despawn(
 }
 
 fn timed_oob_despawn(
-    time: Res<Time>,
+    time: Res<SimulationTime>,
     mut commands: Commands,
     mut query: Query<(Entity, &Transform, &mut TimedOobDespawn)>,
 ) {
@@ -1,10 +1,37 @@
 use super::actor::bullet::BulletType;
+use super::components::DamageKind;
 use bevy::prelude::*;
 
 pub struct LevelEndEvent;
 
-#[derive(Default)]
-pub struct PlayerDeathEvent;
+pub struct PlayerDeathEvent {
+    pub bomb_charges: u32, // left when the ship was lost, the next ship gets them back
+}
+
+// `a` ran into `b`, and deals its damage to `b`
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub damage: i32,
+    pub kind: DamageKind,
+    pub hitmask: u8, // Side of `a`
+    pub point: Vec2, // Roughly where they touch
+}
+
+// Damage about to be dealt to `target`, before armor and shields
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: i32,
+    pub kind: DamageKind,
+    pub scored: bool, // Whether dealt by the player's side
+}
+
+// Health of `entity` ran out, it is despawned once everything has reacted
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub scored: bool, // Whether killed by the player's side
+}
 
 pub struct ScoreEvent {
     pub increment: i32,
@@ -21,9 +48,12 @@ pub struct WeaponFiredEvent {
     pub hitmask: u8,
 }
 
+// Pushes actors within `radius` away from `position`, harder the closer they are
 pub struct ExplosionEvent {
     pub position: Vec3,
     pub lifetime: f32,
+    pub radius: f32,
+    pub impulse: f32,
 }
 
 pub struct CameraShakeEvent {
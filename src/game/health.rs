@@ -1 +1,232 @@
+use bevy::prelude::*;
+use std::cmp::{max, min};
 
+use super::components::*;
+use super::constants::{DEATH_BLAST_IMPULSE, DEATH_BLAST_RADIUS, HIT_FLASH_COLOR, HIT_FLASH_SECS};
+use super::events::*;
+use super::resources::SimulationTime;
+use super::simulation::{SimulationApp, SimulationStep};
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_event::<DamageEvent>()
+            .add_simulation_event::<DeathEvent>()
+            .add_event::<AudioEvent>()
+            .add_simulation_event::<ScoreEvent>()
+            .add_event::<CameraShakeEvent>()
+            .add_simulation_event::<ExplosionEvent>()
+            .add_simulation_event::<PlayerDeathEvent>()
+            .add_simulation_system_set(
+                SimulationStep::Damage,
+                SystemSet::new()
+                    .with_system(apply_damage)
+                    .with_system(update_shields)
+                    .with_system(flash_on_hit.after(apply_damage))
+                    .with_system(award_death_points.after(apply_damage))
+                    .with_system(shake_on_death.after(apply_damage))
+                    .with_system(report_player_death.after(apply_damage))
+                    .with_system(despawn_dead_actors.after(apply_damage)),
+            );
+    }
+}
+
+// Armor first, then shields, whatever is left comes off the health
+pub fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut death_event: EventWriter<DeathEvent>,
+    mut audio_event: EventWriter<AudioEvent>,
+    mut query: Query<(
+        &mut Health,
+        &Transform,
+        Option<&mut Armor>,
+        Option<&mut Shield>,
+    )>,
+) {
+    for damage in damage_events.iter() {
+        let (mut health, transform, armor, shield) = match query.get_mut(damage.target) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+        // Already dying
+        if health.hp == 0 {
+            continue;
+        }
+        let mut amount = match armor {
+            Some(mut armor) => armor.reduce(damage.amount, damage.kind),
+            None => damage.amount,
+        };
+        if let Some(mut shield) = shield {
+            match shield.capacity {
+                None => amount = 0,
+                Some(capacity) => {
+                    let absorbed = min(capacity, amount);
+                    shield.capacity = Some(capacity - absorbed);
+                    amount -= absorbed;
+                    if capacity - absorbed == 0 {
+                        commands.entity(damage.target).remove::<Shield>();
+                    }
+                }
+            }
+        }
+        if amount <= 0 {
+            continue;
+        }
+
+        health.hp = max(health.hp - amount, 0);
+        audio_event.send(AudioEvent {
+            clip: health.damage_sound.clone(),
+        });
+        if health.hp == 0 {
+            death_event.send(DeathEvent {
+                entity: damage.target,
+                position: transform.translation,
+                scored: damage.scored,
+            });
+        } else {
+            commands.entity(damage.target).insert(HitFlash {
+                timer: Timer::from_seconds(HIT_FLASH_SECS, TimerMode::Once),
+            });
+        }
+    }
+}
+
+fn update_shields(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    mut query: Query<(Entity, &mut Shield)>,
+) {
+    for (entity, mut shield) in &mut query {
+        shield.timer.tick(time.delta());
+        if shield.timer.finished() {
+            commands.entity(entity).remove::<Shield>();
+        }
+    }
+}
+
+// Models share their materials, so every mesh of a flashing model gets a tinted
+// copy of its own for the duration of the flash
+fn flash_on_hit(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut flash_query: Query<(Entity, &mut HitFlash)>,
+    children_query: Query<&Children>,
+    mut mesh_query: Query<(&mut Handle<StandardMaterial>, Option<&FlashedMaterial>)>,
+) {
+    for (entity, mut flash) in &mut flash_query {
+        flash.timer.tick(time.delta());
+        let finished = flash.timer.finished();
+        if finished {
+            commands.entity(entity).remove::<HitFlash>();
+        }
+        // The scene may still be spawning its meshes, so look them up every frame
+        let mut descendants = Vec::new();
+        collect_descendants(entity, &children_query, &mut descendants);
+        for descendant in descendants {
+            let (mut material, original) = match mesh_query.get_mut(descendant) {
+                Ok(mesh) => mesh,
+                Err(_) => continue,
+            };
+            match (original, finished) {
+                (Some(original), true) => {
+                    *material = original.0.clone();
+                    commands.entity(descendant).remove::<FlashedMaterial>();
+                }
+                (None, false) => {
+                    let mut tinted = match materials.get(&*material) {
+                        Some(tinted) => tinted.clone(),
+                        None => continue,
+                    };
+                    tinted.emissive = HIT_FLASH_COLOR;
+                    commands
+                        .entity(descendant)
+                        .insert(FlashedMaterial((*material).clone()));
+                    *material = materials.add(tinted);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn collect_descendants(entity: Entity, children_query: &Query<&Children>, out: &mut Vec<Entity>) {
+    if let Ok(children) = children_query.get(entity) {
+        for child in children.iter() {
+            out.push(*child);
+            collect_descendants(*child, children_query, out);
+        }
+    }
+}
+
+fn award_death_points(
+    mut death_events: EventReader<DeathEvent>,
+    mut score_event: EventWriter<ScoreEvent>,
+    query: Query<&DeathPointsAwarded>,
+) {
+    for death in death_events.iter() {
+        if !death.scored {
+            continue;
+        }
+        if let Ok(death_points) = query.get(death.entity) {
+            score_event.send(ScoreEvent {
+                increment: death_points.points,
+            });
+        }
+    }
+}
+
+pub fn shake_on_death(
+    mut death_events: EventReader<DeathEvent>,
+    mut camera_shake_event: EventWriter<CameraShakeEvent>,
+    mut explosion_event: EventWriter<ExplosionEvent>,
+    query: Query<&CameraShakeOnDeath>,
+) {
+    for death in death_events.iter() {
+        if let Ok(shake) = query.get(death.entity) {
+            camera_shake_event.send(CameraShakeEvent {
+                magnitude: shake.magnitude,
+                duration_secs: shake.duration_secs,
+            });
+            explosion_event.send(ExplosionEvent {
+                position: death.position,
+                lifetime: 0.25,
+                radius: DEATH_BLAST_RADIUS,
+                impulse: DEATH_BLAST_IMPULSE,
+            });
+        }
+    }
+}
+
+fn report_player_death(
+    mut death_events: EventReader<DeathEvent>,
+    mut player_death_event: EventWriter<PlayerDeathEvent>,
+    query: Query<Option<&Bomb>, With<Player>>,
+) {
+    for death in death_events.iter() {
+        if let Ok(bomb) = query.get(death.entity) {
+            player_death_event.send(PlayerDeathEvent {
+                bomb_charges: bomb.map_or(0, |bomb| bomb.charges),
+            });
+        }
+    }
+}
+
+// Plays the death sound on the way out
+fn despawn_dead_actors(
+    mut commands: Commands,
+    mut death_events: EventReader<DeathEvent>,
+    mut audio_event: EventWriter<AudioEvent>,
+    query: Query<&Health>,
+) {
+    for death in death_events.iter() {
+        if let Ok(health) = query.get(death.entity) {
+            audio_event.send(AudioEvent {
+                clip: health.death_sound.clone(),
+            });
+            commands.entity(death.entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use super::components::*;
+use super::constants::HIT_KNOCKBACK;
+use super::events::*;
+use super::health::{apply_damage, shake_on_death};
+use super::simulation::{SimulationApp, SimulationStep};
+
+pub struct KinematicsPlugin;
+
+impl Plugin for KinematicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_event::<CollisionEvent>()
+            .add_simulation_event::<ExplosionEvent>()
+            // Impulses land on the step of the hit or blast, and move actors from the next one
+            .add_simulation_system_set(
+                SimulationStep::Damage,
+                SystemSet::new()
+                    .with_system(knock_back_on_hit.after(apply_damage))
+                    .with_system(push_from_explosions.after(shake_on_death)),
+            )
+            .add_simulation_system(SimulationStep::Movement, integrate_kinematics);
+    }
+}
+
+// The only place actors are moved by their velocity. Drag comes off first, so an
+// actor at full thrust holds exactly its max speed.
+pub fn integrate_kinematics(mut query: Query<(&mut Transform, &mut Kinematics)>) {
+    for (mut transform, mut kinematics) in &mut query {
+        let velocity = kinematics.velocity * (1.0 - kinematics.drag);
+        let thrust = kinematics.thrust.clamp_length_max(1.0);
+        // Thrust can turn an actor knocked past its max speed, but not speed it up
+        let limit = velocity.length().max(kinematics.max_speed);
+        kinematics.velocity = (velocity + thrust * kinematics.acceleration).clamp_length_max(limit);
+        kinematics.thrust = Vec2::ZERO;
+        transform.translation.x += kinematics.velocity.x;
+        transform.translation.y += kinematics.velocity.y;
+    }
+}
+
+// Whatever is hit gets pushed away from where it was hit, by how hard it was hit
+fn knock_back_on_hit(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut query: Query<(&Transform, &mut Kinematics), Without<Bullet>>,
+) {
+    for collision in collision_events.iter() {
+        if collision.damage <= 0 {
+            continue;
+        }
+        if let Ok((transform, mut kinematics)) = query.get_mut(collision.b) {
+            let away = (transform.translation.truncate() - collision.point).normalize_or_zero();
+            kinematics.apply_impulse(away * HIT_KNOCKBACK * collision.damage as f32);
+        }
+    }
+}
+
+fn push_from_explosions(
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut query: Query<(&Transform, &mut Kinematics), Without<Bullet>>,
+) {
+    for explosion in explosion_events.iter() {
+        if explosion.impulse <= 0.0 || explosion.radius <= 0.0 {
+            continue;
+        }
+        let center = explosion.position.truncate();
+        for (transform, mut kinematics) in &mut query {
+            let offset = transform.translation.truncate() - center;
+            let distance = offset.length();
+            if distance >= explosion.radius {
+                continue;
+            }
+            let falloff = 1.0 - distance / explosion.radius;
+            kinematics.apply_impulse(offset.normalize_or_zero() * explosion.impulse * falloff);
+        }
+    }
+}
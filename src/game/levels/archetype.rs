@@ -0,0 +1,53 @@
+use serde::Deserialize;
+
+use super::super::actor::ship::*;
+use super::super::actor::BundledActor;
+use super::super::asteroid::{AsteroidField, LargeAsteroid};
+use super::super::boss::BasicBoss;
+use super::super::powerups::*;
+use super::SpawnFunc;
+
+// Names usable in the `archetype` field of a level file.
+// Unknown names are rejected by the RON parser with the line they appear on.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Archetype {
+    DefaultEnemyShip,
+    RaptorSineMovementVariant,
+    JetCharger,
+    SpacePlatformBare,
+    TurretPlatform,
+    MissileCarrier,
+    SineMissileRaptor,
+    BasicBoss,
+    Asteroid,
+    AsteroidField,
+    StarPowerUp,
+    WeaponPowerUp,
+    HealPowerUp,
+    ShieldPowerUp,
+}
+
+impl Archetype {
+    pub fn spawn_func(&self) -> SpawnFunc {
+        match self {
+            Archetype::DefaultEnemyShip => DefaultEnemyShip::spawn_bundle,
+            Archetype::RaptorSineMovementVariant => RaptorSineMovementVariant::spawn_bundle,
+            Archetype::JetCharger => JetCharger::spawn_bundle,
+            Archetype::SpacePlatformBare => SpacePlatformBare::spawn_bundle,
+            Archetype::TurretPlatform => TurretPlatform::spawn_bundle,
+            Archetype::MissileCarrier => MissileCarrier::spawn_bundle,
+            Archetype::SineMissileRaptor => SineMissileRaptor::spawn_bundle,
+            Archetype::BasicBoss => BasicBoss::spawn_bundle,
+            Archetype::Asteroid => LargeAsteroid::spawn_bundle,
+            Archetype::AsteroidField => AsteroidField::spawn_bundle,
+            Archetype::StarPowerUp => StarPowerUp::spawn_bundle,
+            Archetype::WeaponPowerUp => WeaponPowerUp::spawn_bundle,
+            Archetype::HealPowerUp => HealPowerUp::spawn_bundle,
+            Archetype::ShieldPowerUp => ShieldPowerUp::spawn_bundle,
+        }
+    }
+
+    pub fn is_boss(&self) -> bool {
+        return *self == Archetype::BasicBoss;
+    }
+}
@@ -0,0 +1,559 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use super::super::components::AiActorSpawner;
+use super::super::constants::SPAWN_LOCATIONS;
+use super::archetype::Archetype;
+use super::LevelSpawnInfo;
+
+// A level is a list of spawn sequences that run side by side,
+// each one driving its own AiActorSpawner
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "2233140f-fc14-4f18-8974-b870fd4e11bd"]
+pub struct LevelAsset {
+    pub name: String,
+    pub sequences: Vec<LevelSequence>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LevelSequence {
+    pub segments: Vec<LevelSegment>,
+    // Looping sequences start over when they run out and never end the level
+    #[serde(default)]
+    pub looping: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LevelSegment {
+    pub archetype: Archetype,
+    pub locations: SpawnLocations,
+    pub ttl: f32,
+    pub frequency: f32,
+    #[serde(default)]
+    pub until: SegmentEnd,
+    #[serde(default)]
+    pub formation: Formation,
+    #[serde(default)]
+    pub path: SpawnPath,
+}
+
+// How many actors each spawn places, and where relative to the spawn location.
+// The first member leads and the rest keep their place around it.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Formation {
+    #[default]
+    Single,
+    // Vertical column centered on the spawn location
+    Line {
+        count: u32,
+        spacing: f32,
+    },
+    // Leader at the tip, the rest trailing behind on both sides
+    V {
+        count: u32,
+        spacing: f32,
+    },
+    // Evenly spaced ring around the spawn location
+    Circle {
+        count: u32,
+        radius: f32,
+    },
+}
+
+impl Formation {
+    pub fn offsets(&self) -> Vec<Vec2> {
+        match *self {
+            Formation::Single => vec![Vec2::ZERO],
+            Formation::Line { count, spacing } => (0..count)
+                .map(|i| Vec2::new(0.0, (i as f32 - 0.5 * (count - 1) as f32) * spacing))
+                .collect(),
+            Formation::V { count, spacing } => (0..count)
+                .map(|i| {
+                    let row = ((i + 1) / 2) as f32;
+                    let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    Vec2::new(row * spacing, side * row * spacing)
+                })
+                .collect(),
+            Formation::Circle { count, radius } => (0..count)
+                .map(|i| {
+                    let angle = std::f32::consts::TAU * i as f32 / count as f32;
+                    radius * Vec2::new(angle.cos(), angle.sin())
+                })
+                .collect(),
+        }
+    }
+}
+
+// Route taken by the leader of each spawn, in world coordinates
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum SpawnPath {
+    // Movement comes from the archetype's own Ai
+    #[default]
+    Behavior,
+    // Straight lines between the points
+    Waypoints(Vec<(f32, f32)>),
+    // Smooth Catmull-Rom curve through the points
+    Spline(Vec<(f32, f32)>),
+}
+
+// Points sampled along each span of a SpawnPath::Spline
+const SPLINE_SAMPLES: usize = 8;
+
+impl SpawnPath {
+    pub fn resolve(&self) -> Option<Arc<Vec<Vec2>>> {
+        match self {
+            SpawnPath::Behavior => None,
+            SpawnPath::Waypoints(points) => Some(Arc::new(
+                points.iter().map(|(x, y)| Vec2::new(*x, *y)).collect(),
+            )),
+            SpawnPath::Spline(points) => {
+                let points: Vec<Vec2> = points.iter().map(|(x, y)| Vec2::new(*x, *y)).collect();
+                Some(Arc::new(catmull_rom(&points)))
+            }
+        }
+    }
+
+    fn points(&self) -> &[(f32, f32)] {
+        match self {
+            SpawnPath::Behavior => &[],
+            SpawnPath::Waypoints(points) | SpawnPath::Spline(points) => points,
+        }
+    }
+}
+
+// Samples a curve passing through every control point, the end points are repeated
+// so the curve starts and ends on them
+fn catmull_rom(points: &[Vec2]) -> Vec<Vec2> {
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+    let mut samples = Vec::new();
+    for i in 0..points.len() - 1 {
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(points.len() - 1)];
+        for step in 0..SPLINE_SAMPLES {
+            let t = step as f32 / SPLINE_SAMPLES as f32;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            samples.push(
+                0.5 * ((2.0 * p1)
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3),
+            );
+        }
+    }
+    samples.push(points[points.len() - 1]);
+    return samples;
+}
+
+// What moves a sequence on to its next segment
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SegmentEnd {
+    // After `ttl` seconds, spawning every `frequency` seconds
+    #[default]
+    Ttl,
+    // Spawns the boss once and waits for it to be destroyed, `ttl` and `frequency` are unused
+    BossDefeated,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum SpawnLocations {
+    // Every entry of SPAWN_LOCATIONS
+    All,
+    // Indices into SPAWN_LOCATIONS
+    Preset(Vec<usize>),
+    // Arbitrary world coordinates
+    Points(Vec<(f32, f32)>),
+}
+
+impl SpawnLocations {
+    pub fn resolve(&self) -> Vec<Vec2> {
+        match self {
+            SpawnLocations::All => Vec::from(SPAWN_LOCATIONS),
+            SpawnLocations::Preset(indices) => {
+                indices.iter().map(|i| SPAWN_LOCATIONS[*i]).collect()
+            }
+            SpawnLocations::Points(points) => {
+                points.iter().map(|(x, y)| Vec2::new(*x, *y)).collect()
+            }
+        }
+    }
+}
+
+impl LevelSegment {
+    pub fn to_spawn_info(&self) -> LevelSpawnInfo {
+        return LevelSpawnInfo {
+            locations: self.locations.resolve(),
+            ttl: self.ttl,
+            frequency: self.frequency,
+            spawn_func: self.archetype.spawn_func(),
+            until: self.until,
+            formation: self.formation,
+            path: self.path.resolve(),
+        };
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.ttl <= 0.0 {
+            return Err(format!("ttl must be positive, got {}", self.ttl));
+        }
+        if self.frequency <= 0.0 {
+            return Err(format!(
+                "frequency must be positive, got {}",
+                self.frequency
+            ));
+        }
+        if self.until == SegmentEnd::BossDefeated && !self.archetype.is_boss() {
+            return Err(format!(
+                "until: BossDefeated needs a boss archetype, got {:?}",
+                self.archetype
+            ));
+        }
+        match self.formation {
+            Formation::Single => {}
+            Formation::Line { count, spacing } | Formation::V { count, spacing } => {
+                if count == 0 || spacing <= 0.0 {
+                    return Err("formation needs a count and a positive spacing".to_string());
+                }
+            }
+            Formation::Circle { count, radius } => {
+                if count == 0 || radius <= 0.0 {
+                    return Err("formation needs a count and a positive radius".to_string());
+                }
+            }
+        }
+        if let SpawnPath::Waypoints(_) | SpawnPath::Spline(_) = self.path {
+            if self.path.points().is_empty() {
+                return Err("path must not be empty".to_string());
+            }
+        }
+        match &self.locations {
+            SpawnLocations::All => {}
+            SpawnLocations::Preset(indices) => {
+                if indices.is_empty() {
+                    return Err("Preset locations must not be empty".to_string());
+                }
+                if let Some(i) = indices.iter().find(|i| **i >= SPAWN_LOCATIONS.len()) {
+                    return Err(format!(
+                        "Preset location {} is out of range (0..{})",
+                        i,
+                        SPAWN_LOCATIONS.len()
+                    ));
+                }
+            }
+            SpawnLocations::Points(points) => {
+                if points.is_empty() {
+                    return Err("Points locations must not be empty".to_string());
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl LevelAsset {
+    pub fn from_bytes(path: &Path, bytes: &[u8]) -> Result<Self, LevelLoadError> {
+        let level: LevelAsset = ron::de::from_bytes(bytes).map_err(|e| LevelLoadError {
+            path: path.to_path_buf(),
+            position: Some((e.position.line, e.position.col)),
+            message: e.code.to_string(),
+        })?;
+        let source = SourceMap::new(bytes);
+        level
+            .validate(&source)
+            .map_err(|(position, message)| LevelLoadError {
+                path: path.to_path_buf(),
+                position: position,
+                message: message,
+            })?;
+        return Ok(level);
+    }
+
+    // Errors in a sequence or segment point at where it starts in the file
+    fn validate(&self, source: &SourceMap) -> Result<(), (Option<(usize, usize)>, String)> {
+        if self.sequences.is_empty() {
+            return Err((None, "level has no sequences".to_string()));
+        }
+        if self.sequences.iter().all(|s| s.looping) {
+            return Err((
+                None,
+                "level needs at least one sequence that is not looping".to_string(),
+            ));
+        }
+        let mut n_segments = 0;
+        for (i, sequence) in self.sequences.iter().enumerate() {
+            if sequence.segments.is_empty() {
+                return Err((
+                    source.sequences.get(i).copied(),
+                    format!("sequence {} has no segments", i),
+                ));
+            }
+            for (j, segment) in sequence.segments.iter().enumerate() {
+                segment.validate().map_err(|e| {
+                    (
+                        source.segments.get(n_segments).copied(),
+                        format!("sequence {} segment {}: {}", i, j, e),
+                    )
+                })?;
+                n_segments += 1;
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn spawners(&self) -> Vec<AiActorSpawner> {
+        return self
+            .sequences
+            .iter()
+            .map(|sequence| {
+                let mut spawner = AiActorSpawner::new(
+                    sequence
+                        .segments
+                        .iter()
+                        .map(|s| s.to_spawn_info())
+                        .collect(),
+                );
+                spawner.looping = sequence.looping;
+                spawner
+            })
+            .collect();
+    }
+}
+
+// Where every sequence and segment starts in the level file, in the order they
+// were parsed. Found by their required `segments` and `archetype` fields.
+#[derive(Default)]
+struct SourceMap {
+    sequences: Vec<(usize, usize)>,
+    segments: Vec<(usize, usize)>,
+}
+
+impl SourceMap {
+    fn new(bytes: &[u8]) -> Self {
+        let text = match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => return SourceMap::default(),
+        };
+        let positions = |key: &str| -> Vec<(usize, usize)> {
+            return field_offsets(text, key)
+                .into_iter()
+                .map(|offset| line_col(text, offset))
+                .collect();
+        };
+        return SourceMap {
+            sequences: positions("segments"),
+            segments: positions("archetype"),
+        };
+    }
+}
+
+// Offset of every `key:` field in a RON text, skipping strings and comments
+fn field_offsets(text: &str, key: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut offsets = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = match text[i + 2..].find("*/") {
+                    Some(end) => i + 2 + end + 2,
+                    None => bytes.len(),
+                };
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if &text[start..i] == key && text[i..].trim_start().starts_with(':') {
+                    offsets.push(start);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    return offsets;
+}
+
+// 1-based, like the positions of ron's parse errors
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let col = offset - before.rfind('\n').map_or(0, |newline| newline + 1) + 1;
+    return (line, col);
+}
+
+#[derive(Debug)]
+pub struct LevelLoadError {
+    pub path: PathBuf,
+    pub position: Option<(usize, usize)>, // (line, column)
+    pub message: String,
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, col)) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.path.display(),
+                line,
+                col,
+                self.message
+            ),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for LevelLoadError {}
+
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+impl AssetLoader for LevelAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = LevelAsset::from_bytes(load_context.path(), bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL0: &[u8] = include_bytes!("../../../assets/levels/level0.level.ron");
+
+    #[test]
+    fn level0_loads_its_spawn_sequences_in_order() {
+        let level = LevelAsset::from_bytes(Path::new("levels/level0.level.ron"), LEVEL0).unwrap();
+        assert_eq!(level.name, "Level 0");
+        let archetypes: Vec<Archetype> = level.sequences[0]
+            .segments
+            .iter()
+            .map(|s| s.archetype)
+            .collect();
+        assert_eq!(
+            archetypes,
+            vec![
+                Archetype::DefaultEnemyShip,
+                Archetype::JetCharger,
+                Archetype::SpacePlatformBare,
+                Archetype::RaptorSineMovementVariant,
+                Archetype::TurretPlatform,
+                Archetype::RaptorSineMovementVariant,
+                Archetype::JetCharger,
+                Archetype::RaptorSineMovementVariant,
+            ]
+        );
+
+        let spawners = level.spawners();
+        assert_eq!(spawners.len(), 2);
+        assert!(!spawners[0].looping);
+        assert!(spawners[1].looping);
+
+        let enemies = &spawners[0].spawn_infos;
+        let timings: Vec<(f32, f32)> = enemies.iter().map(|s| (s.ttl, s.frequency)).collect();
+        assert_eq!(
+            timings,
+            vec![
+                (10.0, 3.0),
+                (15.0, 1.0),
+                (5.0, 2.0),
+                (30.0, 0.8),
+                (8.0, 2.0),
+                (30.0, 0.8),
+                (15.0, 1.0),
+                (40.0, 0.8),
+            ]
+        );
+        assert_eq!(
+            enemies[0].locations,
+            vec![SPAWN_LOCATIONS[0], SPAWN_LOCATIONS[1], SPAWN_LOCATIONS[3]]
+        );
+        for spawn_info in &enemies[1..] {
+            assert_eq!(spawn_info.locations, Vec::from(SPAWN_LOCATIONS));
+        }
+
+        // The spawner starts on its first segment
+        assert_eq!(spawners[0].index, 0);
+        assert_eq!(spawners[0].ttl_timer.duration().as_secs_f32(), 10.0);
+        assert_eq!(spawners[0].frequency_timer.duration().as_secs_f32(), 3.0);
+    }
+
+    #[test]
+    fn broken_levels_report_where_they_failed() {
+        let path = Path::new("levels/broken.level.ron");
+        let error = LevelAsset::from_bytes(path, b"(name: \"Broken\", sequences: [").unwrap_err();
+        assert!(error.position.is_some());
+
+        let error = LevelAsset::from_bytes(path, b"(name: \"Empty\", sequences: [])").unwrap_err();
+        assert_eq!(error.position, None);
+        assert_eq!(error.message, "level has no sequences");
+    }
+
+    #[test]
+    fn invalid_segments_report_their_line() {
+        let level = r#"(
+    name: "Broken",
+    sequences: [
+        (
+            // archetype: is only looked for outside comments
+            segments: [
+                (archetype: DefaultEnemyShip, locations: All, ttl: 10.0, frequency: 1.0),
+                (
+                    archetype: JetCharger,
+                    locations: Preset([0, 99]),
+                    ttl: 10.0,
+                    frequency: 1.0,
+                ),
+            ],
+        ),
+    ],
+)"#;
+        let path = Path::new("levels/broken.level.ron");
+        let error = LevelAsset::from_bytes(path, level.as_bytes()).unwrap_err();
+        assert_eq!(error.position, Some((9, 21)));
+        assert!(error
+            .message
+            .starts_with("sequence 0 segment 1: Preset location 99"));
+    }
+}
@@ -1,4 +1,5 @@
-use bevy::{prelude::*, time::FixedTimestep, utils::Duration};
+use bevy::{asset::LoadState, prelude::*, utils::Duration};
+use std::sync::Arc;
 
 use crate::utils::despawn_all;
 
@@ -7,91 +8,204 @@ use super::actor::{ship::*, *};
 use super::components::*;
 use super::constants::*;
 use super::events::LevelEndEvent;
+use super::resources::{GameRng, SimulationTime};
+use super::simulation::{SimulationApp, SimulationStep};
 use super::{super::*, scene, AudioClipAssets, SceneAssets};
-use fastrand;
-pub mod lvl;
-use lvl::*;
+pub mod archetype;
+pub mod asset;
+use asset::*;
+
+pub type SpawnFunc =
+    fn(&mut Commands, &Res<AudioClipAssets>, &Res<SceneAssets>, &fastrand::Rng, Vec2) -> Entity;
 
 // FIXME: Use enum rather than bundle here to make this
 // capable of spawning any type of bundle!
+#[derive(Clone)]
 pub struct LevelSpawnInfo {
     pub locations: Vec<Vec2>,
     pub ttl: f32,
     pub frequency: f32,
-    pub spawn_func: fn(&mut Commands, &Res<AudioClipAssets>, &Res<SceneAssets>, Vec2),
+    pub spawn_func: SpawnFunc,
+    pub until: SegmentEnd,
+    pub formation: Formation,
+    pub path: Option<Arc<Vec<Vec2>>>,
+}
+
+// Levels are played in this order, the campaign ends after the last one
+const LEVEL_PATHS: [&str; 3] = [
+    "levels/level0.level.ron",
+    "levels/level1.level.ron",
+    "levels/level2.level.ron",
+];
+
+// Registry of every level in the campaign
+#[derive(Resource)]
+pub struct LevelAssets {
+    pub levels: Vec<Handle<LevelAsset>>,
+}
+
+#[derive(Resource, Default)]
+pub struct CurrentLevel {
+    pub index: usize,
 }
 
+// Inserted when a level starts, removed once the level asset is loaded
+// and its spawners have been created
+#[derive(Resource)]
+struct PendingLevel(Handle<LevelAsset>);
+
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<LevelEndEvent>()
+        app.add_asset::<LevelAsset>()
+            .init_asset_loader::<LevelAssetLoader>()
+            .init_resource::<CurrentLevel>()
+            .add_startup_system(load_levels)
+            .add_simulation_event::<LevelEndEvent>()
             .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup_level))
+            .add_simulation_system_set(
+                SimulationStep::Spawn,
+                SystemSet::new()
+                    .with_system(spawn_pending_level)
+                    .with_system(level_periodic_spawn)
+                    // Sees the end of the level on the step the last spawner runs out
+                    .with_system(level_ender.after(level_periodic_spawn)),
+            )
             .add_system_set(
                 SystemSet::on_exit(AppState::InGame).with_system(despawn_all::<AiActorSpawner>),
-            )
-            .add_system(level_periodic_spawn)
-            .add_system(level_ender);
+            );
     }
 }
 
+fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelAssets {
+        levels: LEVEL_PATHS
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+    });
+}
+
 fn setup_level(
     mut commands: Commands,
-    audio_clips: Res<AudioClipAssets>,
-    models: Res<SceneAssets>,
+    levels: Res<LevelAssets>,
+    mut current_level: ResMut<CurrentLevel>,
 ) {
-    commands.spawn(AiActorSpawner::new(SpawnSequence::level0(
-        &audio_clips,
-        &models,
-    )));
-    commands.spawn(AiActorSpawner::new(SpawnSequence::level0_powerups(
-        &audio_clips,
-        &models,
-    )));
+    // Every run starts from the first level
+    current_level.index = 0;
+    commands.insert_resource(PendingLevel(levels.levels[0].clone()));
+}
+
+fn spawn_pending_level(
+    mut commands: Commands,
+    pending: Option<Res<PendingLevel>>,
+    asset_server: Res<AssetServer>,
+    level_assets: Res<Assets<LevelAsset>>,
+) {
+    let pending = match pending {
+        Some(pending) => pending,
+        None => return,
+    };
+    if let Some(level) = level_assets.get(&pending.0) {
+        info!("Starting level: {}", level.name);
+        for spawner in level.spawners() {
+            commands.spawn(spawner);
+        }
+        commands.remove_resource::<PendingLevel>();
+    } else if asset_server.get_load_state(&pending.0) == LoadState::Failed {
+        // The asset server has already logged the parse error with its file and line
+        error!("Level could not be loaded, no enemies will spawn");
+        commands.remove_resource::<PendingLevel>();
+    }
 }
 
 fn level_periodic_spawn(
     mut commands: Commands,
-    time: Res<Time>,
+    time: Res<SimulationTime>,
     models: Res<SceneAssets>,
     audio_clips: Res<AudioClipAssets>,
+    mut game_rng: ResMut<GameRng>,
     mut level_end_event: EventWriter<LevelEndEvent>,
-    mut query: Query<&mut AiActorSpawner, With<AiActorSpawner>>,
+    mut query: Query<(Entity, &mut AiActorSpawner)>,
+    boss_query: Query<(), With<Boss>>,
 ) {
+    let mut n_running = 0;
+    let mut n_finished = 0;
     // Run logic for each Spawner Component
-    for mut spawner in &mut query {
+    for (entity, mut spawner) in &mut query {
         let n_spawn_infos = spawner.spawn_infos.len() as i32;
         // Tick spawn timer
         spawner.frequency_timer.tick(time.delta());
         spawner.ttl_timer.tick(time.delta());
 
+        let segment_over = match spawner.spawn_infos[spawner.index as usize].until {
+            SegmentEnd::Ttl => spawner.ttl_timer.just_finished(),
+            SegmentEnd::BossDefeated => spawner.boss_spawned && boss_query.is_empty(),
+        };
+
         // Fixme: Make this more functional
-        if spawner.ttl_timer.just_finished() {
+        if segment_over {
             spawner.index += 1;
-            if spawner.index < n_spawn_infos {
-                // Get the next spawn info and set the frequency and ttl durations
-                let next_ttl = spawner.spawn_infos[spawner.index as usize].ttl;
-                let next_frequency = spawner.spawn_infos[spawner.index as usize].frequency;
-                spawner
-                    .ttl_timer
-                    .set_duration(Duration::from_secs_f32(next_ttl));
-                spawner
-                    .frequency_timer
-                    .set_duration(Duration::from_secs_f32(next_frequency));
-
-                spawner.ttl_timer.reset();
-            } else {
+            spawner.boss_spawned = false;
+            if spawner.index >= n_spawn_infos {
+                if !spawner.looping {
+                    // This sequence has run out
+                    commands.entity(entity).despawn_recursive();
+                    n_finished += 1;
+                    continue;
+                }
                 spawner.index = 0;
-                level_end_event.send(LevelEndEvent {});
             }
-        }
+            // Get the next spawn info and set the frequency and ttl durations
+            let next_ttl = spawner.spawn_infos[spawner.index as usize].ttl;
+            let next_frequency = spawner.spawn_infos[spawner.index as usize].frequency;
+            spawner
+                .ttl_timer
+                .set_duration(Duration::from_secs_f32(next_ttl));
+            spawner
+                .frequency_timer
+                .set_duration(Duration::from_secs_f32(next_frequency));
 
-        if spawner.frequency_timer.finished() {
-            let spawn_info = &spawner.spawn_infos[spawner.index as usize];
+            spawner.ttl_timer.reset();
+        }
+        if !spawner.looping {
+            n_running += 1;
+        }
 
-            spawn_from_spawn_info(&mut commands, spawn_info, &audio_clips, &models);
+        let spawn_info = &spawner.spawn_infos[spawner.index as usize];
+        match spawn_info.until {
+            SegmentEnd::Ttl => {
+                if spawner.frequency_timer.finished() {
+                    spawn_from_spawn_info(
+                        &mut commands,
+                        spawn_info,
+                        &audio_clips,
+                        &models,
+                        &mut game_rng,
+                    );
+                }
+            }
+            SegmentEnd::BossDefeated => {
+                // Bosses are spawned once, as soon as their segment starts
+                if !spawner.boss_spawned {
+                    spawn_from_spawn_info(
+                        &mut commands,
+                        spawn_info,
+                        &audio_clips,
+                        &models,
+                        &mut game_rng,
+                    );
+                    spawner.boss_spawned = true;
+                }
+            }
         }
     }
+
+    // The level ends once every non-looping sequence has run out
+    if n_finished > 0 && n_running == 0 {
+        level_end_event.send(LevelEndEvent {});
+    }
 }
 
 fn spawn_from_spawn_info(
@@ -99,24 +213,66 @@ fn spawn_from_spawn_info(
     spawn_info: &LevelSpawnInfo,
     audio_clips: &Res<AudioClipAssets>,
     models: &Res<SceneAssets>,
+    game_rng: &mut GameRng,
 ) {
     // Read from spawn info
-    let rng = fastrand::Rng::new();
+    let rng = game_rng.gameplay.fork();
     let spawn_pos = spawn_info.locations[rng.usize(0..spawn_info.locations.len())];
+    let offsets = spawn_info.formation.offsets();
     // Note: function must be wrapped in parenthesis
     // ref: https://stackoverflow.com/questions/37370120/
-    (spawn_info.spawn_func)(commands, &audio_clips, &models, spawn_pos);
+    let leader = (spawn_info.spawn_func)(
+        commands,
+        &audio_clips,
+        &models,
+        &rng,
+        spawn_pos + offsets[0],
+    );
+    if let Some(points) = &spawn_info.path {
+        commands.entity(leader).insert(FollowPath {
+            points: points.clone(),
+            index: 0,
+        });
+    }
+    for offset in &offsets[1..] {
+        let member =
+            (spawn_info.spawn_func)(commands, &audio_clips, &models, &rng, spawn_pos + *offset);
+        commands.entity(member).insert(FormationSlot {
+            leader: leader,
+            offset: *offset - offsets[0],
+        });
+    }
 }
 
 fn level_ender(
+    mut commands: Commands,
     mut events: EventReader<LevelEndEvent>,
     mut game_state: ResMut<State<AppState>>,
     mut menu_state: ResMut<State<MenuState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut sim_time: ResMut<SimulationTime>,
+    levels: Res<LevelAssets>,
+    spawners: Query<Entity, With<AiActorSpawner>>,
 ) {
-    if !events.is_empty() {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+
+    if current_level.index + 1 < levels.levels.len() {
+        // Move on to the next level without leaving the game,
+        // so the player keeps their score and health
+        current_level.index += 1;
+        for entity in &spawners {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands.insert_resource(PendingLevel(levels.levels[current_level.index].clone()));
+    } else {
+        sim_time.halt();
         // Use overwrite_set for events, since events may register over multiple frames
-        menu_state.overwrite_set(MenuState::LevelEnd).unwrap();
+        menu_state
+            .overwrite_set(MenuState::CampaignComplete)
+            .unwrap();
         game_state.overwrite_set(AppState::Menu).unwrap();
-        events.clear();
     }
 }
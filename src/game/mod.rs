@@ -7,32 +7,56 @@ use bevy::{prelude::*, scene::ScenePlugin};
 mod ai;
 pub use ai::*;
 
+mod simulation;
+pub use simulation::SimulationPlugin;
+
+pub mod controls;
+use controls::ControlsPlugin;
+
 mod actor;
 use actor::ActorPlugin;
 
 mod audio;
 pub use audio::*;
 
-mod constants;
+pub mod constants;
 use constants::*;
 
-mod player;
+pub mod player;
 use player::*;
 
 mod collisions;
 pub use collisions::*;
 
+mod broadphase;
+
+mod narrowphase;
+
+#[cfg(test)]
+mod bench;
+
 mod health;
 pub use health::*;
 
+mod kinematics;
+use kinematics::KinematicsPlugin;
+
 pub mod components;
 pub use components::Player;
 
-mod events;
+pub mod events;
+
+pub mod resources;
+pub use resources::RunSeed;
 
-mod resources;
+mod rng;
+pub use rng::RngPlugin;
 
-mod ui;
+mod replay;
+pub use replay::ReplayMode;
+use replay::ReplayPlugin;
+
+pub mod ui;
 use ui::UiPlugin;
 
 mod despawner;
@@ -53,6 +77,18 @@ pub use weapon::WeaponPlugin;
 mod vfx;
 pub use vfx::VfxPlugin;
 
+mod powerups;
+pub use powerups::PowerUpPlugin;
+
+mod turret;
+pub use turret::TurretPlugin;
+
+mod boss;
+pub use boss::BossPlugin;
+
+mod asteroid;
+pub use asteroid::AsteroidPlugin;
+
 mod scene;
 pub use scene::SceneAssets;
 
@@ -65,7 +101,12 @@ impl Plugin for GamePlugin {
             .add_plugin(ScenePlugin)
             //.add_plugin(HanabiPlugin) //Incompatible with WASM
             //.add_plugin(ParticleSystemPlugin)
+            // Before every plugin that adds systems to the simulation
+            .add_plugin(SimulationPlugin)
+            .add_plugin(RngPlugin)
             .add_plugin(BackgroundPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(ReplayPlugin)
             .add_plugin(UiPlugin)
             .add_plugin(AiPlugin)
             .add_plugin(AudioPlugin)
@@ -73,10 +114,15 @@ impl Plugin for GamePlugin {
             .add_plugin(PlayerPlugin)
             .add_plugin(WeaponPlugin)
             .add_plugin(CollisionPlugin)
-            .add_plugin(WeaponPlugin)
+            .add_plugin(HealthPlugin)
+            .add_plugin(KinematicsPlugin)
             .add_plugin(LevelPlugin)
             .add_plugin(DespawnerPlugin)
             .add_plugin(VfxPlugin)
+            .add_plugin(PowerUpPlugin)
+            .add_plugin(TurretPlugin)
+            .add_plugin(BossPlugin)
+            .add_plugin(AsteroidPlugin)
             .add_plugin(ActorPlugin); // currently for cleaning up entities
     }
 }
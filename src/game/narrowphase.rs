@@ -0,0 +1,393 @@
+use super::components::{Collider, ColliderShape};
+use bevy::prelude::*;
+
+// Where a collider sits on screen. Sizes are in model units, so they grow with the
+// Transform scale, and shapes turn with the Transform about the screen normal.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    center: Vec2,
+    rotation: Vec2, // (cos, sin) of the screen-space angle
+    scale: Vec2,
+}
+
+impl Frame {
+    fn of(transform: &Transform) -> Self {
+        // Models are turned about y to face sideways, which leaves their up axis alone,
+        // so the up axis alone tells how far they have turned on screen
+        let up = (transform.rotation * Vec3::Y).truncate();
+        let rotation = match up.try_normalize() {
+            Some(up) => Vec2::new(up.y, -up.x),
+            None => Vec2::X,
+        };
+        return Frame {
+            center: transform.translation.truncate(),
+            rotation: rotation,
+            scale: transform.scale.truncate().abs(),
+        };
+    }
+
+    // Frame of a compound part placed at `offset`
+    fn offset(&self, offset: Vec2) -> Self {
+        return Frame {
+            center: self.center + self.rotation.rotate(offset * self.scale),
+            ..*self
+        };
+    }
+}
+
+// A single shape resolved to world coordinates
+#[derive(Clone, Copy, Debug)]
+enum Placed {
+    Box {
+        center: Vec2,
+        axes: [Vec2; 2],
+        half: Vec2,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+}
+
+impl Collider {
+    // Narrowphase test, touching edges do not count as a hit
+    pub fn intersects(
+        &self,
+        transform: &Transform,
+        other: &Collider,
+        other_transform: &Transform,
+    ) -> bool {
+        return overlaps(
+            &self.shape,
+            Frame::of(transform),
+            &other.shape,
+            Frame::of(other_transform),
+        );
+    }
+
+    // Corners of the axis-aligned box around the whole shape
+    pub fn bounds(&self, transform: &Transform) -> (Vec2, Vec2) {
+        return shape_bounds(&self.shape, Frame::of(transform));
+    }
+}
+
+fn place(shape: &ColliderShape, frame: Frame) -> Option<Placed> {
+    return match shape {
+        ColliderShape::Aabb(size) => Some(Placed::Box {
+            center: frame.center,
+            axes: [Vec2::X, Vec2::Y],
+            half: 0.5 * *size * frame.scale,
+        }),
+        ColliderShape::Obb(size) => Some(Placed::Box {
+            center: frame.center,
+            axes: [frame.rotation, frame.rotation.perp()],
+            half: 0.5 * *size * frame.scale,
+        }),
+        ColliderShape::Circle(radius) => Some(Placed::Circle {
+            center: frame.center,
+            radius: *radius * frame.scale.max_element(),
+        }),
+        ColliderShape::Compound(_) => None,
+    };
+}
+
+fn overlaps(a: &ColliderShape, a_frame: Frame, b: &ColliderShape, b_frame: Frame) -> bool {
+    // Compounds hit when any of their parts do
+    if let ColliderShape::Compound(parts) = a {
+        return parts
+            .iter()
+            .any(|(offset, part)| overlaps(part, a_frame.offset(*offset), b, b_frame));
+    }
+    if let ColliderShape::Compound(parts) = b {
+        return parts
+            .iter()
+            .any(|(offset, part)| overlaps(a, a_frame, part, b_frame.offset(*offset)));
+    }
+    return match (place(a, a_frame), place(b, b_frame)) {
+        (
+            Some(Placed::Circle {
+                center: a_center,
+                radius: a_radius,
+            }),
+            Some(Placed::Circle {
+                center: b_center,
+                radius: b_radius,
+            }),
+        ) => a_center.distance_squared(b_center) < (a_radius + b_radius).powi(2),
+        (
+            Some(Placed::Box { center, axes, half }),
+            Some(Placed::Circle {
+                center: circle,
+                radius,
+            }),
+        )
+        | (
+            Some(Placed::Circle {
+                center: circle,
+                radius,
+            }),
+            Some(Placed::Box { center, axes, half }),
+        ) => box_circle(center, axes, half, circle, radius),
+        (
+            Some(Placed::Box {
+                center: a_center,
+                axes: a_axes,
+                half: a_half,
+            }),
+            Some(Placed::Box {
+                center: b_center,
+                axes: b_axes,
+                half: b_half,
+            }),
+        ) => box_box(a_center, a_axes, a_half, b_center, b_axes, b_half),
+        _ => false,
+    };
+}
+
+// Closest point of the box to the circle's center, found in the box's own frame
+fn box_circle(center: Vec2, axes: [Vec2; 2], half: Vec2, circle: Vec2, radius: f32) -> bool {
+    let d = circle - center;
+    let local = Vec2::new(d.dot(axes[0]), d.dot(axes[1]));
+    let closest = local.clamp(-half, half);
+    return local.distance_squared(closest) < radius * radius;
+}
+
+// Separating axis test, the only candidate axes for two boxes are their edge normals
+fn box_box(
+    a_center: Vec2,
+    a_axes: [Vec2; 2],
+    a_half: Vec2,
+    b_center: Vec2,
+    b_axes: [Vec2; 2],
+    b_half: Vec2,
+) -> bool {
+    let d = b_center - a_center;
+    for axis in a_axes.iter().chain(b_axes.iter()) {
+        let a_extent =
+            a_half.x * a_axes[0].dot(*axis).abs() + a_half.y * a_axes[1].dot(*axis).abs();
+        let b_extent =
+            b_half.x * b_axes[0].dot(*axis).abs() + b_half.y * b_axes[1].dot(*axis).abs();
+        if d.dot(*axis).abs() >= a_extent + b_extent {
+            return false;
+        }
+    }
+    return true;
+}
+
+fn shape_bounds(shape: &ColliderShape, frame: Frame) -> (Vec2, Vec2) {
+    if let ColliderShape::Compound(parts) = shape {
+        let mut bounds = (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN));
+        for (offset, part) in parts {
+            let (min, max) = shape_bounds(part, frame.offset(*offset));
+            bounds = (bounds.0.min(min), bounds.1.max(max));
+        }
+        return bounds;
+    }
+    let (center, extent) = match place(shape, frame) {
+        Some(Placed::Box { center, axes, half }) => {
+            (center, half.x * axes[0].abs() + half.y * axes[1].abs())
+        }
+        Some(Placed::Circle { center, radius }) => (center, Vec2::splat(radius)),
+        None => (frame.center, Vec2::ZERO),
+    };
+    return (center - extent, center + extent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::components::DamageKind;
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    fn collider(shape: ColliderShape) -> Collider {
+        return Collider {
+            shape: shape,
+            damage: 0,
+            damage_kind: DamageKind::default(),
+            hitmask: 0,
+        };
+    }
+
+    fn at(x: f32, y: f32) -> Transform {
+        return Transform::from_xyz(x, y, 0.0);
+    }
+
+    fn turned(x: f32, y: f32) -> Transform {
+        return at(x, y).with_rotation(Quat::from_rotation_z(FRAC_PI_4));
+    }
+
+    // Both ways round give the same answer
+    fn hits(
+        a: &ColliderShape,
+        a_transform: Transform,
+        b: &ColliderShape,
+        b_transform: Transform,
+    ) -> bool {
+        let (a, b) = (collider(a.clone()), collider(b.clone()));
+        let hit = a.intersects(&a_transform, &b, &b_transform);
+        assert_eq!(hit, b.intersects(&b_transform, &a, &a_transform));
+        return hit;
+    }
+
+    fn square() -> ColliderShape {
+        return ColliderShape::Aabb(Vec2::new(2.0, 2.0));
+    }
+
+    fn turning_square() -> ColliderShape {
+        return ColliderShape::Obb(Vec2::new(2.0, 2.0));
+    }
+
+    fn circle() -> ColliderShape {
+        return ColliderShape::Circle(1.0);
+    }
+
+    // A square on the right and a circle on the left, with a gap in the middle
+    fn dumbbell() -> ColliderShape {
+        return ColliderShape::Compound(vec![
+            (Vec2::new(3.0, 0.0), square()),
+            (Vec2::new(-3.0, 0.0), circle()),
+        ]);
+    }
+
+    #[test]
+    fn aabb_aabb() {
+        assert!(hits(&square(), at(0.0, 0.0), &square(), at(1.5, 0.5)));
+        assert!(!hits(&square(), at(0.0, 0.0), &square(), at(2.0, 0.0)));
+        assert!(!hits(&square(), at(0.0, 0.0), &square(), at(3.0, 0.0)));
+        // Turning doesn't change an Aabb
+        assert!(!hits(&square(), turned(0.0, 0.0), &square(), at(2.2, 0.0)));
+    }
+
+    #[test]
+    fn aabb_circle() {
+        assert!(hits(&square(), at(0.0, 0.0), &circle(), at(1.5, 0.0)));
+        assert!(!hits(&square(), at(0.0, 0.0), &circle(), at(2.0, 0.0)));
+        assert!(!hits(&square(), at(0.0, 0.0), &circle(), at(3.0, 0.0)));
+        // Past the corner, where the bounding boxes would still overlap
+        assert!(!hits(&square(), at(0.0, 0.0), &circle(), at(1.8, 1.8)));
+    }
+
+    #[test]
+    fn aabb_obb() {
+        assert!(hits(
+            &square(),
+            at(0.0, 0.0),
+            &turning_square(),
+            turned(2.3, 0.0)
+        ));
+        assert!(!hits(
+            &square(),
+            at(0.0, 0.0),
+            &turning_square(),
+            at(2.0, 0.0)
+        ));
+        assert!(!hits(
+            &square(),
+            at(0.0, 0.0),
+            &turning_square(),
+            turned(2.5, 0.0)
+        ));
+    }
+
+    #[test]
+    fn aabb_compound() {
+        assert!(hits(&square(), at(4.5, 0.0), &dumbbell(), at(0.0, 0.0)));
+        assert!(!hits(&square(), at(6.0, 0.0), &dumbbell(), at(0.0, 0.0)));
+        assert!(!hits(&square(), at(0.0, 0.0), &dumbbell(), at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn circle_circle() {
+        assert!(hits(&circle(), at(0.0, 0.0), &circle(), at(1.5, 0.0)));
+        assert!(!hits(&circle(), at(0.0, 0.0), &circle(), at(2.0, 0.0)));
+        assert!(!hits(&circle(), at(0.0, 0.0), &circle(), at(3.0, 0.0)));
+        // The radius grows with the largest scale
+        let scaled = at(3.0, 0.0).with_scale(Vec3::new(1.0, 2.5, 1.0));
+        assert!(hits(&circle(), at(0.0, 0.0), &circle(), scaled));
+    }
+
+    #[test]
+    fn circle_obb() {
+        // The corner of the turned box reaches out to about 1.41
+        assert!(hits(
+            &circle(),
+            at(2.3, 0.0),
+            &turning_square(),
+            turned(0.0, 0.0)
+        ));
+        assert!(!hits(
+            &circle(),
+            at(2.0, 0.0),
+            &turning_square(),
+            at(0.0, 0.0)
+        ));
+        assert!(!hits(
+            &circle(),
+            at(2.5, 0.0),
+            &turning_square(),
+            turned(0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn circle_compound() {
+        assert!(hits(&circle(), at(-4.5, 0.0), &dumbbell(), at(0.0, 0.0)));
+        assert!(!hits(&circle(), at(5.0, 0.0), &dumbbell(), at(0.0, 0.0)));
+        assert!(!hits(&circle(), at(0.0, 0.0), &dumbbell(), at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn obb_obb() {
+        assert!(hits(
+            &turning_square(),
+            turned(0.0, 0.0),
+            &turning_square(),
+            turned(2.5, 0.0)
+        ));
+        assert!(!hits(
+            &turning_square(),
+            at(0.0, 0.0),
+            &turning_square(),
+            at(0.0, 2.0)
+        ));
+        assert!(!hits(
+            &turning_square(),
+            turned(0.0, 0.0),
+            &turning_square(),
+            turned(3.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn obb_compound() {
+        assert!(hits(
+            &turning_square(),
+            turned(5.0, 0.0),
+            &dumbbell(),
+            at(0.0, 0.0)
+        ));
+        assert!(!hits(
+            &turning_square(),
+            at(5.0, 0.0),
+            &dumbbell(),
+            at(0.0, 0.0)
+        ));
+        assert!(!hits(
+            &turning_square(),
+            turned(0.0, 0.0),
+            &dumbbell(),
+            at(0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn compound_compound() {
+        assert!(hits(&dumbbell(), at(0.0, 0.0), &dumbbell(), at(1.5, 0.0)));
+        assert!(!hits(&dumbbell(), at(0.0, 0.0), &dumbbell(), at(0.0, 2.0)));
+        assert!(!hits(&dumbbell(), at(0.0, 0.0), &dumbbell(), at(0.0, 3.0)));
+        // Parts keep their place as the whole shape is scaled
+        let scaled = at(0.0, 0.0).with_scale(Vec3::splat(2.0));
+        assert!(hits(&dumbbell(), scaled, &square(), at(8.5, 0.0)));
+        assert!(!hits(&dumbbell(), scaled, &square(), at(9.0, 0.0)));
+    }
+}
@@ -4,19 +4,21 @@ use super::super::AppState;
 use super::actor::ship::PlayerShipDefault;
 use super::actor::BundledActor;
 use super::actor::PlayerActorBundle;
-use super::collisions::check_collisions;
-use super::collisions::CollisionEvent;
 use super::components::*;
-use super::constants::PLAYER_SPAWN_POS;
+use super::constants::{PLAYER_LIVES, PLAYER_SPAWN_POS, RESPAWN_DELAY};
+use super::controls::{Action, ActionState};
 use super::events::WeaponFiredEvent;
-use super::events::{AudioEvent, PlayerDeathEvent};
+use super::events::{AudioEvent, CollisionEvent, PlayerDeathEvent};
+use super::kinematics::integrate_kinematics;
+use super::resources::{PlayerLives, RespawnTimer, SimulationTime};
 use super::scene;
+use super::simulation::{SimulationApp, SimulationStep};
+use super::weapon::volley_events;
 use super::AudioClipAssets;
 use super::SceneAssets;
 use bevy::{
     prelude::*,
     sprite::collide_aabb::{collide, Collision},
-    time::FixedTimestep,
 };
 
 pub struct PlayerPlugin;
@@ -25,23 +27,29 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(scene::setup_resources) //why does this need to be explicitly specified?
-            .add_event::<WeaponFiredEvent>()
-            .add_event::<CollisionEvent>()
+            .add_simulation_event::<WeaponFiredEvent>()
+            .add_simulation_event::<CollisionEvent>()
             .add_event::<AudioEvent>()
-            .add_event::<PlayerDeathEvent>()
-            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(spawn_player))
+            .add_simulation_event::<PlayerDeathEvent>()
+            .insert_resource(PlayerLives {
+                lives: PLAYER_LIVES,
+            })
             .add_system_set(
-                SystemSet::on_update(AppState::InGame)
-                    //.with_run_criteria(FixedTimestep::step(TIMESTEPas f64))
-                    .with_system(player_controller.before(check_collisions))
-                    .with_system(fire_controller)
-                    .with_system(
-                        reflect_from_wall
-                            .before(check_collisions)
-                            .after(player_controller),
-                    ),
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(spawn_player)
+                    .with_system(reset_lives),
+            )
+            .add_simulation_system(SimulationStep::Spawn, respawn_player)
+            .add_simulation_system(SimulationStep::Weapons, fire_controller)
+            .add_simulation_system_set(
+                SimulationStep::Movement,
+                SystemSet::new()
+                    .with_system(player_controller.before(integrate_kinematics))
+                    .with_system(reflect_from_wall.after(integrate_kinematics)),
             )
-            .add_system(on_player_death);
+            .add_simulation_system(SimulationStep::Damage, blink_invulnerable)
+            // In the simulation, so lives, respawns and game over land on the same step every time
+            .add_simulation_system(SimulationStep::Despawn, on_player_death);
     }
 }
 
@@ -61,59 +69,55 @@ pub fn spawn_player(
 
 // Player controller system
 fn player_controller(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut ship_query: Query<(&mut Transform, &Actor), With<Player>>,
+    actions: Res<ActionState>,
+    mut ship_query: Query<&mut Kinematics, With<Player>>,
 ) {
-    for (mut ship_transform, ship) in &mut ship_query {
-        let mut direction_x: f32 = 0.0;
-        let mut direction_y = 0.0;
-
-        if keyboard_input.pressed(KeyCode::Down) {
-            direction_y -= ship.speed.y;
-        }
-
-        if keyboard_input.pressed(KeyCode::Up) {
-            direction_y += ship.speed.y;
-        }
-
-        if keyboard_input.pressed(KeyCode::Left) {
-            direction_x -= ship.speed.x;
-        }
-
-        if keyboard_input.pressed(KeyCode::Right) {
-            direction_x += ship.speed.x;
-        }
-
-        // Calculate the new position based on player input
-        ship_transform.translation.y = ship_transform.translation.y + direction_y;
-        ship_transform.translation.x = ship_transform.translation.x + direction_x;
+    for mut ship in &mut ship_query {
+        // Sticks fly the ship partway, keys always at full speed
+        let movement = actions.movement().clamp_length_max(1.0);
+        let max_speed = ship.max_speed;
+        ship.steer(movement * max_speed);
     }
 }
 
 pub fn reflect_from_wall(
-    mut ship_query: Query<(&mut Transform, &Collider, &Actor), With<Player>>,
+    mut ship_query: Query<(&mut Transform, &Collider, &mut Kinematics), With<Player>>,
     wall_query: Query<&Transform, (With<Wall>, Without<Player>)>,
 ) {
-    for (mut ship_transform, ship_collider, ship) in &mut ship_query {
+    for (mut ship_transform, ship_collider, mut ship) in &mut ship_query {
         // FIXME: Call this from player_controller to eliminate redundant transform query
         // Bounce back on wall collision
         for wall_transform in &wall_query {
             let mut direction_x: f32 = 0.0;
             let mut direction_y = 0.0;
 
+            let (ship_min, ship_max) = ship_collider.bounds(&ship_transform);
             let collision = collide(
                 wall_transform.translation,
                 wall_transform.scale.truncate(),
-                ship_transform.translation,
-                ship_collider.rect,
+                (0.5 * (ship_min + ship_max)).extend(ship_transform.translation.z),
+                ship_max - ship_min,
             );
 
+            // Velocity into the wall bounces back off it
             if let Some(collision) = collision {
                 match collision {
-                    Collision::Left => direction_x += ship.speed.x,
-                    Collision::Right => direction_x -= ship.speed.x,
-                    Collision::Top => direction_y -= ship.speed.y,
-                    Collision::Bottom => direction_y += ship.speed.y,
+                    Collision::Left => {
+                        direction_x += ship.max_speed;
+                        ship.velocity.x = ship.velocity.x.abs();
+                    }
+                    Collision::Right => {
+                        direction_x -= ship.max_speed;
+                        ship.velocity.x = -ship.velocity.x.abs();
+                    }
+                    Collision::Top => {
+                        direction_y -= ship.max_speed;
+                        ship.velocity.y = -ship.velocity.y.abs();
+                    }
+                    Collision::Bottom => {
+                        direction_y += ship.max_speed;
+                        ship.velocity.y = ship.velocity.y.abs();
+                    }
                     Collision::Inside => { /* do nothing */ }
                 }
             }
@@ -126,33 +130,32 @@ pub fn reflect_from_wall(
 
 // Fire controller system
 pub fn fire_controller(
-    time: Res<Time>,
-    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<SimulationTime>,
+    actions: Res<ActionState>,
     mut audio_event: EventWriter<AudioEvent>,
     mut bullet_fired_event: EventWriter<WeaponFiredEvent>,
     mut query: Query<(&Transform, &mut Weapon, &Collider), With<Player>>,
 ) {
     for (transform, mut weapon, collider) in &mut query {
-        if keyboard_input.just_pressed(KeyCode::Space) {
+        if actions.just_pressed(Action::Fire) {
             weapon.cooldown_timer.reset();
             weapon.cooldown_timer.set_mode(TimerMode::Repeating);
             weapon.cooldown_timer.unpause();
-        } else if keyboard_input.just_released(KeyCode::Space) {
+        } else if actions.just_released(Action::Fire) {
             weapon.cooldown_timer.pause()
         }
         weapon.cooldown_timer.tick(time.delta());
 
+        let mut fire = weapon.tick_burst(time.delta());
         if weapon.cooldown_timer.just_finished() {
-            let event = WeaponFiredEvent {
-                bullet_type: weapon.bullet_type.clone(),
-                translation: Vec2::new(
-                    transform.translation.x + weapon.offset.x * transform.forward().x,
-                    transform.translation.y + weapon.offset.y * transform.forward().y,
-                ),
-                rotation: transform.rotation,
-                hitmask: collider.hitmask, // Bullets have the same hitmask as the collider attached to the firer
-            };
-            bullet_fired_event.send(event);
+            weapon.start_burst();
+            fire = true;
+        }
+        if fire {
+            // Bullets have the same hitmask as the collider attached to the firer
+            for event in volley_events(&weapon, transform, collider.hitmask) {
+                bullet_fired_event.send(event);
+            }
             audio_event.send(AudioEvent {
                 clip: weapon.firing_audio_clip.clone(),
             })
@@ -160,16 +163,81 @@ pub fn fire_controller(
     }
 }
 
+pub fn reset_lives(mut commands: Commands, mut lives: ResMut<PlayerLives>) {
+    lives.lives = PLAYER_LIVES;
+    commands.remove_resource::<RespawnTimer>();
+}
+
 fn on_player_death(
-    death_events: EventReader<PlayerDeathEvent>,
+    mut commands: Commands,
+    mut death_events: EventReader<PlayerDeathEvent>,
+    mut lives: ResMut<PlayerLives>,
     mut menu_state: ResMut<State<MenuState>>,
     mut game_state: ResMut<State<AppState>>,
+    mut sim_time: ResMut<SimulationTime>,
     //query: Query<Entity, With<Player>>
 ) {
-    if !death_events.is_empty() {
-        // Currently panics
-        menu_state.overwrite_set(MenuState::PlayerDeath).unwrap();
+    if let Some(death) = death_events.iter().last() {
+        lives.lives = lives.lives.saturating_sub(1);
+        if lives.lives > 0 {
+            commands.insert_resource(RespawnTimer {
+                timer: Timer::from_seconds(RESPAWN_DELAY, TimerMode::Once),
+                bomb_charges: death.bomb_charges,
+            });
+            return;
+        }
+        // A death that lands after the game already left InGame (e.g. on the
+        // same step as the campaign ending) must not set AppState::Menu twice
+        if sim_time.halted || *game_state.current() != AppState::InGame {
+            return;
+        }
+        sim_time.halt();
         game_state.overwrite_set(AppState::Menu).unwrap();
-        death_events.clear();
+        menu_state.overwrite_set(MenuState::PlayerDeath).unwrap();
+    }
+}
+
+fn respawn_player(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    respawn_timer: Option<ResMut<RespawnTimer>>,
+    audio_clips: Res<AudioClipAssets>,
+    models: Res<SceneAssets>,
+) {
+    if let Some(mut respawn_timer) = respawn_timer {
+        respawn_timer.timer.tick(time.delta());
+        if respawn_timer.timer.finished() {
+            // Bombs used up stay used up, a new ship doesn't refill them
+            commands
+                .spawn((
+                    PlayerShipDefault::get_bundle(&audio_clips, &models, PLAYER_SPAWN_POS),
+                    Invulnerable,
+                    RespawnInvulnerability::default(),
+                ))
+                .insert(Bomb {
+                    charges: respawn_timer.bomb_charges,
+                });
+            commands.remove_resource::<RespawnTimer>();
+        }
+    }
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    mut query: Query<(Entity, &mut Visibility, &mut RespawnInvulnerability)>,
+) {
+    for (entity, mut visibility, mut invulnerability) in &mut query {
+        invulnerability.timer.tick(time.delta());
+        invulnerability.blink_timer.tick(time.delta());
+        if invulnerability.timer.finished() {
+            visibility.is_visible = true;
+            commands
+                .entity(entity)
+                .remove::<Invulnerable>()
+                .remove::<RespawnInvulnerability>();
+        } else if invulnerability.blink_timer.just_finished() {
+            visibility.is_visible = !visibility.is_visible;
+        }
     }
 }
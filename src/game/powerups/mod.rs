@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+use std::cmp::min;
+
+use super::super::AppState;
+use super::actor::{BundledActor, StarRustSceneBundle};
+use super::ai::{AiBehavior, Movement};
+use super::components::*;
+use super::constants::*;
+use super::events::AudioEvent;
+use super::resources::{ScoreMultiplier, SimulationTime};
+use super::simulation::{SimulationApp, SimulationStep};
+use super::{AudioClipAssets, SceneAssets};
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoreMultiplier>()
+            .add_event::<AudioEvent>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame).with_system(reset_score_multiplier),
+            )
+            .add_simulation_system(SimulationStep::Collisions, collect_power_ups)
+            .add_simulation_system(SimulationStep::Damage, update_score_multiplier);
+    }
+}
+
+#[derive(Bundle, Clone)]
+pub struct PowerUpBundle {
+    pub power_up: PowerUp,
+    pub actor: Actor,
+    pub kinematics: Kinematics,
+    pub scene_bundle: StarRustSceneBundle,
+    pub collider: Collider,
+    pub ai: Ai,
+    pub timed_oob_despawn: TimedOobDespawn,
+}
+
+impl PowerUpBundle {
+    pub fn new(
+        effect: PowerUpEffect,
+        scene: Handle<Scene>,
+        audio_clips: &Res<AudioClipAssets>,
+        spawn_position: Vec2,
+    ) -> Self {
+        return PowerUpBundle {
+            power_up: PowerUp {
+                effect: effect,
+                pickup_sound: audio_clips.collection1.clone(),
+            },
+            actor: Actor,
+            kinematics: Kinematics::new(4.2),
+            scene_bundle: StarRustSceneBundle {
+                scene: scene,
+                transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 2.0)
+                    .with_scale(Vec3::splat(POWER_UP_SCALE))
+                    .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
+                ..default()
+            },
+            collider: Collider {
+                shape: ColliderShape::Circle(10.0 / POWER_UP_SCALE),
+                damage: 0,
+                damage_kind: DamageKind::Kinetic,
+                hitmask: ALLY_HITMASK,
+            },
+            ai: Ai::new(AiBehavior::single(Movement::Forward)),
+            timed_oob_despawn: TimedOobDespawn { ..default() },
+        };
+    }
+}
+
+pub struct StarPowerUp;
+
+impl BundledActor<PowerUpBundle> for StarPowerUp {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PowerUpBundle {
+        let mut bundle = PowerUpBundle::new(
+            PowerUpEffect::ScoreMultiplier {
+                factor: 2,
+                duration_secs: 10.0,
+            },
+            models.powerup_star.clone(),
+            audio_clips,
+            spawn_position,
+        );
+        bundle.power_up.pickup_sound = audio_clips.coin_larry.clone();
+        bundle.kinematics = Kinematics::new(8.5);
+        return bundle;
+    }
+}
+
+pub struct WeaponPowerUp;
+
+impl BundledActor<PowerUpBundle> for WeaponPowerUp {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PowerUpBundle {
+        return PowerUpBundle::new(
+            PowerUpEffect::WeaponUpgrade,
+            models.powerup_ico.clone(),
+            audio_clips,
+            spawn_position,
+        );
+    }
+}
+
+pub struct HealPowerUp;
+
+impl BundledActor<PowerUpBundle> for HealPowerUp {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PowerUpBundle {
+        return PowerUpBundle::new(
+            PowerUpEffect::Heal(5),
+            models.powerup_ico.clone(),
+            audio_clips,
+            spawn_position,
+        );
+    }
+}
+
+pub struct ShieldPowerUp;
+
+impl BundledActor<PowerUpBundle> for ShieldPowerUp {
+    fn get_bundle(
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        spawn_position: Vec2,
+    ) -> PowerUpBundle {
+        return PowerUpBundle::new(
+            PowerUpEffect::Shield { duration_secs: 5.0 },
+            models.powerup_ico.clone(),
+            audio_clips,
+            spawn_position,
+        );
+    }
+}
+
+fn collect_power_ups(
+    mut commands: Commands,
+    mut audio_event: EventWriter<AudioEvent>,
+    mut score_multiplier: ResMut<ScoreMultiplier>,
+    mut player_query: Query<
+        (Entity, &Transform, &Collider, &mut Health, &mut Weapon),
+        With<Player>,
+    >,
+    power_up_query: Query<(Entity, &Transform, &Collider, &PowerUp)>,
+) {
+    for (player, player_transform, player_collider, mut health, mut weapon) in &mut player_query {
+        for (power_up_entity, transform, collider, power_up) in &power_up_query {
+            if !collider.intersects(transform, player_collider, player_transform) {
+                continue;
+            }
+
+            match power_up.effect {
+                PowerUpEffect::WeaponUpgrade => weapon.upgrade(),
+                PowerUpEffect::Heal(amount) => {
+                    health.hp = min(health.hp + amount, health.max_hp);
+                }
+                PowerUpEffect::Shield { duration_secs } => {
+                    commands.entity(player).insert(Shield {
+                        capacity: None,
+                        timer: Timer::from_seconds(duration_secs, TimerMode::Once),
+                    });
+                }
+                PowerUpEffect::ScoreMultiplier {
+                    factor,
+                    duration_secs,
+                } => {
+                    score_multiplier.factor = factor;
+                    score_multiplier.timer = Timer::from_seconds(duration_secs, TimerMode::Once);
+                }
+            }
+
+            audio_event.send(AudioEvent {
+                clip: power_up.pickup_sound.clone(),
+            });
+            commands.entity(power_up_entity).despawn_recursive();
+        }
+    }
+}
+
+fn update_score_multiplier(
+    time: Res<SimulationTime>,
+    mut score_multiplier: ResMut<ScoreMultiplier>,
+) {
+    score_multiplier.timer.tick(time.delta());
+    if score_multiplier.timer.just_finished() {
+        score_multiplier.factor = 1;
+    }
+}
+
+fn reset_score_multiplier(mut score_multiplier: ResMut<ScoreMultiplier>) {
+    *score_multiplier = ScoreMultiplier::default();
+}
@@ -0,0 +1,109 @@
+// A recorded run: its seed and the actions of every step. Given the same seed
+// and the same actions, the simulation plays out the same run again.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::super::constants::REPLAY_VERSION;
+use super::super::controls::Action;
+
+// Each action held between 0 (released) and 255 (fully held)
+pub type StepInput = [u8; Action::ALL.len()];
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    pub steps: u64,
+    pub score: i32,
+    pub deaths: Vec<u64>, // the step of every player death
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    // Runs of identical steps, most steps repeat the one before
+    pub inputs: Vec<(u32, StepInput)>,
+    pub outcome: ReplayOutcome,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        return Replay {
+            version: REPLAY_VERSION,
+            seed: seed,
+            inputs: Vec::new(),
+            outcome: ReplayOutcome::default(),
+        };
+    }
+
+    pub fn push(&mut self, input: StepInput) {
+        self.outcome.steps += 1;
+        if let Some((count, last)) = self.inputs.last_mut() {
+            if *last == input {
+                *count += 1;
+                return;
+            }
+        }
+        self.inputs.push((1, input));
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let replay: Replay = ron::de::from_str(&text).map_err(|e| e.to_string())?;
+        replay.validate()?;
+        return Ok(replay);
+    }
+
+    // Written on one line, a replay is read by the game and not by people
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string(self).map_err(|e| e.to_string())?;
+        return fs::write(path, text).map_err(|e| e.to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != REPLAY_VERSION {
+            return Err(format!(
+                "recorded by replay version {}, this game plays version {}",
+                self.version, REPLAY_VERSION
+            ));
+        }
+        if self.inputs.iter().any(|(count, _)| *count == 0) {
+            return Err("empty run of steps".to_string());
+        }
+        let steps: u64 = self.inputs.iter().map(|(count, _)| *count as u64).sum();
+        if steps != self.outcome.steps {
+            return Err(format!(
+                "{} steps of input for a run of {} steps",
+                steps, self.outcome.steps
+            ));
+        }
+        return Ok(());
+    }
+}
+
+// Reads the inputs of a replay back one step at a time
+#[derive(Clone, Debug, Default)]
+pub struct ReplayCursor {
+    run: usize,
+    offset: u32,
+}
+
+impl ReplayCursor {
+    pub fn next(&mut self, replay: &Replay) -> Option<StepInput> {
+        let (count, input) = replay.inputs.get(self.run)?;
+        self.offset += 1;
+        if self.offset >= *count {
+            self.run += 1;
+            self.offset = 0;
+        }
+        return Some(*input);
+    }
+}
+
+pub fn quantize(values: [f32; Action::ALL.len()]) -> StepInput {
+    return values.map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8);
+}
+
+pub fn dequantize(input: StepInput) -> [f32; Action::ALL.len()] {
+    return input.map(|value| value as f32 / 255.0);
+}
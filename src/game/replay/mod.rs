@@ -0,0 +1,256 @@
+use bevy::{app::AppExit, prelude::*};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::super::menus::MenuState;
+use super::super::AppState;
+use super::constants::{FAST_FORWARD_KEY, REPLAY_DIR};
+use super::controls::{ActionState, ControlsSystem};
+use super::events::PlayerDeathEvent;
+use super::resources::{GameRng, RunSeed, Scoreboard, SimulationTime};
+use super::simulation::{SimulationApp, SimulationStep};
+pub mod file;
+pub use file::*;
+
+// Every run is recorded, unless a recording is being played back instead
+#[derive(Resource)]
+pub enum ReplayMode {
+    Record(Replay),
+    Playback(ReplayPlayer),
+}
+
+impl Default for ReplayMode {
+    fn default() -> Self {
+        return ReplayMode::Record(Replay::new(0));
+    }
+}
+
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    pub cursor: ReplayCursor,
+    pub actual: ReplayOutcome, // what happened this time, to check against the recording
+    pub started: bool,
+    pub fast_forward: bool,
+}
+
+impl ReplayMode {
+    // `--replay <file>` plays a recording, `--fast-forward` plays it at FAST_FORWARD_SPEED
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let args: Vec<String> = args.collect();
+        let position = match args.iter().position(|arg| arg == "--replay") {
+            Some(position) => position,
+            None => return Ok(ReplayMode::default()),
+        };
+        let path = args
+            .get(position + 1)
+            .ok_or("--replay needs the path of a replay file")?;
+        let replay = Replay::load(Path::new(path)).map_err(|e| format!("{}: {}", path, e))?;
+        return Ok(ReplayMode::Playback(ReplayPlayer {
+            replay: replay,
+            cursor: ReplayCursor::default(),
+            actual: ReplayOutcome::default(),
+            started: false,
+            fast_forward: args.iter().any(|arg| arg == "--fast-forward"),
+        }));
+    }
+
+    // A recording is played back with the seed it was recorded with
+    pub fn run_seed(&self) -> Option<RunSeed> {
+        return match self {
+            ReplayMode::Playback(player) => Some(RunSeed::Fixed(player.replay.seed)),
+            ReplayMode::Record(_) => None,
+        };
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(start_run))
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(finish_run))
+            .add_system(start_playback)
+            .add_system(toggle_fast_forward)
+            // After every input source, so the player systems see the actions of the replay
+            .add_simulation_system(
+                SimulationStep::Input,
+                replay_actions.after(ControlsSystem::Read),
+            )
+            .add_simulation_system(SimulationStep::Record, record_deaths);
+    }
+}
+
+// Starts the run like the play button does, once
+fn start_playback(
+    mut mode: ResMut<ReplayMode>,
+    mut sim_time: ResMut<SimulationTime>,
+    mut menu_state: ResMut<State<MenuState>>,
+    mut game_state: ResMut<State<AppState>>,
+) {
+    if let ReplayMode::Playback(player) = mode.as_mut() {
+        if player.started || *game_state.current() != AppState::Menu {
+            return;
+        }
+        player.started = true;
+        sim_time.fast_forward = player.fast_forward;
+        info!(
+            "Playing back {} steps with seed {}",
+            player.replay.outcome.steps, player.replay.seed
+        );
+        menu_state.overwrite_set(MenuState::Disabled).unwrap();
+        game_state.overwrite_set(AppState::InGame).unwrap();
+    }
+}
+
+fn toggle_fast_forward(
+    mode: Res<ReplayMode>,
+    keys: Res<Input<KeyCode>>,
+    mut sim_time: ResMut<SimulationTime>,
+) {
+    if let ReplayMode::Playback(_) = *mode {
+        if keys.just_pressed(FAST_FORWARD_KEY) {
+            sim_time.fast_forward = !sim_time.fast_forward;
+        }
+    }
+}
+
+fn start_run(mut mode: ResMut<ReplayMode>) {
+    match mode.as_mut() {
+        ReplayMode::Record(replay) => *replay = Replay::new(0),
+        ReplayMode::Playback(player) => {
+            player.cursor = ReplayCursor::default();
+            player.actual = ReplayOutcome::default();
+        }
+    }
+}
+
+// Recorded actions are quantized the same way while playing live, so a live run
+// and its replay see exactly the same values
+fn replay_actions(
+    mut mode: ResMut<ReplayMode>,
+    mut actions: ResMut<ActionState>,
+    mut sim_time: ResMut<SimulationTime>,
+    mut menu_state: ResMut<State<MenuState>>,
+    mut game_state: ResMut<State<AppState>>,
+) {
+    match mode.as_mut() {
+        ReplayMode::Record(replay) => {
+            let input = quantize(actions.values());
+            actions.set_values(dequantize(input));
+            replay.push(input);
+        }
+        // Whatever is pressed live is ignored
+        ReplayMode::Playback(player) => {
+            let input = player.cursor.next(&player.replay).unwrap_or_default();
+            actions.set_values(dequantize(input));
+            player.actual.steps += 1;
+            // The recorded run ended on this step, even if it was quit rather than lost
+            if player.actual.steps >= player.replay.outcome.steps && !sim_time.halted {
+                sim_time.halt();
+                menu_state.overwrite_set(MenuState::Main).unwrap();
+                game_state.overwrite_set(AppState::Menu).unwrap();
+            }
+        }
+    }
+}
+
+fn record_deaths(
+    mut mode: ResMut<ReplayMode>,
+    mut death_events: EventReader<PlayerDeathEvent>,
+    sim_time: Res<SimulationTime>,
+) {
+    for _ in death_events.iter() {
+        match mode.as_mut() {
+            ReplayMode::Record(replay) => replay.outcome.deaths.push(sim_time.steps),
+            ReplayMode::Playback(player) => {
+                let index = player.actual.deaths.len();
+                if player.replay.outcome.deaths.get(index) != Some(&sim_time.steps) {
+                    warn!(
+                        "Replay desync: player died at step {}, recorded death {} was at step {:?}",
+                        sim_time.steps,
+                        index + 1,
+                        player.replay.outcome.deaths.get(index)
+                    );
+                }
+                player.actual.deaths.push(sim_time.steps);
+            }
+        }
+    }
+}
+
+// Saves the recording, or checks the playback against it and quits
+fn finish_run(
+    mut mode: ResMut<ReplayMode>,
+    scoreboard: Res<Scoreboard>,
+    game_rng: Res<GameRng>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match mode.as_mut() {
+        ReplayMode::Record(replay) => {
+            replay.seed = game_rng.seed;
+            replay.outcome.score = scoreboard.score;
+            match save_replay(replay) {
+                Ok(path) => info!("Run recorded to {}", path.display()),
+                Err(e) => warn!("Could not save the replay: {}", e),
+            }
+        }
+        ReplayMode::Playback(player) => {
+            player.actual.score = scoreboard.score;
+            match find_desync(&player.replay.outcome, &player.actual) {
+                Some(desync) => error!("Replay desync: {}", desync),
+                None => info!(
+                    "Replay matches the recording: score {} after {} steps",
+                    player.actual.score, player.actual.steps
+                ),
+            }
+            exit.send(AppExit);
+        }
+    }
+}
+
+fn save_replay(replay: &Replay) -> Result<PathBuf, String> {
+    fs::create_dir_all(REPLAY_DIR).map_err(|e| e.to_string())?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = Path::new(REPLAY_DIR).join(format!("run-{}-{}.ron", now.as_secs(), replay.seed));
+    replay.save(&path)?;
+    return Ok(path);
+}
+
+// The first way the played back run differs from the recorded one
+fn find_desync(expected: &ReplayOutcome, actual: &ReplayOutcome) -> Option<String> {
+    let deaths = expected.deaths.iter().zip(actual.deaths.iter()).enumerate();
+    for (index, (expected_step, actual_step)) in deaths {
+        if expected_step != actual_step {
+            return Some(format!(
+                "death {} at step {}, recorded at step {}",
+                index + 1,
+                actual_step,
+                expected_step
+            ));
+        }
+    }
+    if expected.deaths.len() != actual.deaths.len() {
+        return Some(format!(
+            "{} deaths, recorded {}",
+            actual.deaths.len(),
+            expected.deaths.len()
+        ));
+    }
+    if expected.steps != actual.steps {
+        return Some(format!(
+            "run ended after {} steps, recorded {}",
+            actual.steps, expected.steps
+        ));
+    }
+    if expected.score != actual.score {
+        return Some(format!(
+            "final score {}, recorded {}",
+            actual.score, expected.score
+        ));
+    }
+    return None;
+}
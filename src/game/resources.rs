@@ -1,4 +1,7 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::Duration};
+
+use super::constants::{COSMETIC_RNG_SALT, FAST_FORWARD_SPEED, SECONDS_PER_DAY, TIME_STEP};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // Scoreboard resource
 #[derive(Resource)]
@@ -7,6 +10,178 @@ pub struct Scoreboard {
 }
 
 #[derive(Resource)]
-struct HealthIndicator {
-    hp: i8,
+pub struct PlayerLives {
+    pub lives: u32,
+}
+
+// Counts down until the player respawns after losing a life
+#[derive(Resource)]
+pub struct RespawnTimer {
+    pub timer: Timer,
+    pub bomb_charges: u32,
+}
+
+// Applied to points gained until the timer runs out
+#[derive(Resource)]
+pub struct ScoreMultiplier {
+    pub factor: i32,
+    pub timer: Timer,
+}
+
+impl Default for ScoreMultiplier {
+    fn default() -> Self {
+        return ScoreMultiplier {
+            factor: 1,
+            timer: Timer::default(),
+        };
+    }
+}
+
+impl ScoreMultiplier {
+    pub fn apply(&self, increment: i32) -> i32 {
+        if increment < 0 {
+            return increment;
+        }
+        return increment * self.factor;
+    }
+}
+
+// How check_collisions finds the pairs worth testing, both resolve the same hits
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Broadphase {
+    // Every collider against every actor, only picked by the tests to check the grid against
+    #[allow(dead_code)]
+    BruteForce,
+    // Only pairs sharing a cell of a uniform grid
+    #[default]
+    Grid,
+}
+
+// Clock of the fixed-step simulation, read by gameplay systems in place of Time
+#[derive(Resource, Default)]
+pub struct SimulationTime {
+    pub steps: u64,
+    pub accumulator: f64, // real time not yet simulated
+    pub looping: bool,    // set while steps are being run this frame
+    pub halted: bool,     // the run is over, no more steps until the next one starts
+    pub fast_forward: bool,
+}
+
+impl SimulationTime {
+    pub fn delta(&self) -> Duration {
+        return Duration::from_secs_f32(TIME_STEP);
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        return TIME_STEP;
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        return self.steps as f32 * TIME_STEP;
+    }
+
+    // No step after this one, so a run always ends on the step that ended it
+    pub fn halt(&mut self) {
+        self.halted = true;
+    }
+
+    // Steps run for each step's worth of real time
+    pub fn speed(&self) -> u32 {
+        if self.fast_forward {
+            return FAST_FORWARD_SPEED;
+        }
+        return 1;
+    }
+
+    // How far into the next step rendering is, between 0 and 1
+    pub fn overstep_fraction(&self) -> f32 {
+        return (self.accumulator / TIME_STEP as f64).min(1.0) as f32;
+    }
+}
+
+// Where the seed of each run comes from
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RunSeed {
+    // Same run every time, to replay or share one
+    Fixed(u64),
+    // Same run for everyone on the same (UTC) day
+    Daily,
+    #[default]
+    Random,
+}
+
+impl RunSeed {
+    // `--seed <number>` or `--daily`, a random seed otherwise
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let args: Vec<String> = args.collect();
+        if args.iter().any(|arg| arg == "--daily") {
+            return Ok(RunSeed::Daily);
+        }
+        let position = match args.iter().position(|arg| arg == "--seed") {
+            Some(position) => position,
+            None => return Ok(RunSeed::Random),
+        };
+        return match args.get(position + 1).map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => Ok(RunSeed::Fixed(seed)),
+            _ => Err("--seed needs a whole number".to_string()),
+        };
+    }
+
+    pub fn resolve(&self) -> u64 {
+        return match self {
+            RunSeed::Fixed(seed) => *seed,
+            RunSeed::Daily => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                now.as_secs() / SECONDS_PER_DAY
+            }
+            RunSeed::Random => fastrand::u64(..),
+        };
+    }
+}
+
+// One random sequence. Every use forks off a generator of its own, and the
+// stream moves on past it, so the draws only depend on the seed and the order of use.
+#[derive(Clone, Debug)]
+pub struct RngStream {
+    state: u64,
+}
+
+impl RngStream {
+    pub fn new(seed: u64) -> Self {
+        return RngStream { state: seed };
+    }
+
+    pub fn fork(&mut self) -> fastrand::Rng {
+        let rng = fastrand::Rng::with_seed(self.state);
+        self.state = rng.u64(..);
+        return rng;
+    }
+}
+
+// Randomness of the current run, reseeded whenever a run starts. Effects draw from
+// their own stream, so however much they draw it never changes what happens in the game.
+// No two systems drawing from the same stream may run unordered within a stage.
+#[derive(Resource, Clone, Debug)]
+pub struct GameRng {
+    pub seed: u64,
+    pub gameplay: RngStream,
+    pub cosmetic: RngStream,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        return GameRng {
+            seed: seed,
+            gameplay: RngStream::new(seed),
+            cosmetic: RngStream::new(seed ^ COSMETIC_RNG_SALT),
+        };
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        return GameRng::new(0);
+    }
 }
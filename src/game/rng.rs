@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+use super::super::AppState;
+use super::resources::{GameRng, RunSeed};
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunSeed>()
+            .init_resource::<GameRng>()
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(seed_run));
+    }
+}
+
+// Logged so that any run can be played again with `--seed`
+fn seed_run(run_seed: Res<RunSeed>, mut rng: ResMut<GameRng>) {
+    let seed = run_seed.resolve();
+    info!("Run seed {} ({:?})", seed, *run_seed);
+    *rng = GameRng::new(seed);
+}
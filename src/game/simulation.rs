@@ -0,0 +1,188 @@
+// Gameplay runs in fixed steps of TIME_STEP, however fast frames are rendered.
+// Each step goes through the same sub-stages in the same order, so a step only
+// depends on the state before it and the input sampled at its start.
+use bevy::{
+    ecs::{event::Event, schedule::ShouldRun},
+    prelude::*,
+    transform::TransformSystem,
+};
+
+use super::super::AppState;
+use super::components::{Actor, Interpolated};
+use super::constants::{MAX_STEPS_PER_FRAME, TIME_STEP};
+use super::resources::SimulationTime;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StageLabel)]
+pub struct SimulationStage;
+
+// The sub-stages of a step, in the order they run. Commands are applied between
+// them, so whatever one spawns or despawns is seen by the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, StageLabel)]
+pub enum SimulationStep {
+    Input,
+    Spawn,
+    Ai,
+    Weapons,
+    Movement,
+    Collisions,
+    Damage,
+    Despawn,
+    Record,
+}
+
+impl SimulationStep {
+    pub const ALL: [SimulationStep; 9] = [
+        SimulationStep::Input,
+        SimulationStep::Spawn,
+        SimulationStep::Ai,
+        SimulationStep::Weapons,
+        SimulationStep::Movement,
+        SimulationStep::Collisions,
+        SimulationStep::Damage,
+        SimulationStep::Despawn,
+        SimulationStep::Record,
+    ];
+}
+
+// Lets plugins add their systems to a sub-stage of the simulation
+pub trait SimulationApp {
+    fn add_simulation_system<Params>(
+        &mut self,
+        step: SimulationStep,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self;
+    fn add_simulation_system_set(&mut self, step: SimulationStep, set: SystemSet) -> &mut Self;
+    // In place of add_event for events sent and read by the simulation. They are kept
+    // until the end of the step after the one they were sent in, however many frames
+    // go by in between, rather than being dropped after two frames.
+    fn add_simulation_event<T: Event>(&mut self) -> &mut Self;
+}
+
+impl SimulationApp for App {
+    fn add_simulation_system<Params>(
+        &mut self,
+        step: SimulationStep,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self {
+        return self.stage(SimulationStage, |schedule: &mut Schedule| {
+            return schedule.add_system_to_stage(step, system);
+        });
+    }
+
+    fn add_simulation_system_set(&mut self, step: SimulationStep, set: SystemSet) -> &mut Self {
+        return self.stage(SimulationStage, |schedule: &mut Schedule| {
+            return schedule.add_system_set_to_stage(step, set);
+        });
+    }
+
+    fn add_simulation_event<T: Event>(&mut self) -> &mut Self {
+        if self.world.contains_resource::<Events<T>>() {
+            return self;
+        }
+        return self
+            .init_resource::<Events<T>>()
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(clear_events::<T>))
+            .add_simulation_system(SimulationStep::Input, Events::<T>::update_system);
+    }
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let mut schedule = Schedule::default().with_run_criteria(run_simulation);
+        for step in SimulationStep::ALL {
+            schedule.add_stage(step, SystemStage::parallel());
+        }
+        app.init_resource::<SimulationTime>()
+            .add_stage_before(CoreStage::Update, SimulationStage, schedule)
+            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(reset_clock))
+            .add_simulation_system(SimulationStep::Input, restore_transforms)
+            .add_simulation_system(SimulationStep::Record, record_transforms)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// Runs as many steps as the time since the last frame pays for. Time spent
+// paused or in the menus is never simulated.
+fn run_simulation(
+    time: Res<Time>,
+    app_state: Res<State<AppState>>,
+    mut sim_time: ResMut<SimulationTime>,
+) -> ShouldRun {
+    if *app_state.current() != AppState::InGame || sim_time.halted {
+        sim_time.looping = false;
+        return ShouldRun::No;
+    }
+    // First check of the frame
+    if !sim_time.looping {
+        sim_time.looping = true;
+        // After a long stall, drop the time that can't be caught up rather than
+        // freezing on a pile of steps
+        let speed = sim_time.speed() as f64;
+        sim_time.accumulator = (sim_time.accumulator + time.delta_seconds_f64() * speed)
+            .min(MAX_STEPS_PER_FRAME as f64 * TIME_STEP as f64 * speed);
+    }
+    if sim_time.accumulator < TIME_STEP as f64 {
+        sim_time.looping = false;
+        return ShouldRun::No;
+    }
+    sim_time.accumulator -= TIME_STEP as f64;
+    sim_time.steps += 1;
+    return ShouldRun::YesAndCheckAgain;
+}
+
+// Every run starts from step zero, time-based movement depends on it
+fn reset_clock(mut sim_time: ResMut<SimulationTime>) {
+    *sim_time = SimulationTime {
+        fast_forward: sim_time.fast_forward,
+        ..default()
+    };
+}
+
+// Nothing sent in the last steps of the previous run reaches the new one
+fn clear_events<T: Event>(mut events: ResMut<Events<T>>) {
+    events.clear();
+}
+
+// Undoes the blend shown last frame, the step carries on from where the last one ended
+fn restore_transforms(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in &mut query {
+        transform.translation = interpolated.current.0;
+        transform.rotation = interpolated.current.1;
+        interpolated.previous = interpolated.current;
+    }
+}
+
+// Actors spawned this step start out where they were placed
+fn record_transforms(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Interpolated)>,
+    new_query: Query<(Entity, &Transform), (With<Actor>, Without<Interpolated>)>,
+) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.current = (transform.translation, transform.rotation);
+    }
+    for (entity, transform) in &new_query {
+        commands.entity(entity).insert(Interpolated {
+            previous: (transform.translation, transform.rotation),
+            current: (transform.translation, transform.rotation),
+        });
+    }
+}
+
+// Shows actors part of the way between the last two steps, by how much of the
+// next step has already gone by
+pub fn interpolate_transforms(
+    sim_time: Res<SimulationTime>,
+    mut query: Query<(&mut Transform, &Interpolated)>,
+) {
+    let alpha = sim_time.overstep_fraction();
+    for (mut transform, interpolated) in &mut query {
+        transform.translation = interpolated.previous.0.lerp(interpolated.current.0, alpha);
+        transform.rotation = interpolated.previous.1.slerp(interpolated.current.1, alpha);
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use super::actor::bullet::BulletType;
+use super::actor::{ActorBundle, StarRustSceneBundle};
+use super::ai::steer_towards;
+use super::components::*;
+use super::constants::*;
+use super::kinematics::integrate_kinematics;
+use super::resources::SimulationTime;
+use super::simulation::{interpolate_transforms, SimulationApp, SimulationStep};
+use super::{AudioClipAssets, SceneAssets};
+
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_simulation_system(
+            SimulationStep::Movement,
+            update_turrets.after(integrate_kinematics),
+        )
+        // Follows the rendered turret rather than the simulated one
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            steady_turret_bases
+                .after(interpolate_transforms)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+#[derive(Bundle, Clone)]
+pub struct TurretBundle {
+    pub actor_bundle: ActorBundle,
+    pub auto_fire: AutoFire,
+    pub death_points_awarded: DeathPointsAwarded,
+    pub turret: Turret,
+}
+
+impl TurretBundle {
+    pub fn new(
+        anchor: Entity,
+        offset: Vec2,
+        audio_clips: &Res<AudioClipAssets>,
+        models: &Res<SceneAssets>,
+        anchor_position: Vec2,
+    ) -> Self {
+        let position = anchor_position + offset;
+        return TurretBundle {
+            actor_bundle: ActorBundle {
+                actor: Actor,
+                kinematics: Kinematics::anchored(),
+                scene_bundle: StarRustSceneBundle {
+                    scene: models.turret_barrel.clone(),
+                    transform: Transform::from_xyz(position.x, position.y, 3.0)
+                        .with_scale(Vec3::splat(ASSET_SCALE))
+                        .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
+                    ..default()
+                },
+                collider: Collider {
+                    shape: ColliderShape::Circle(15.0 / ASSET_SCALE),
+                    damage: 1,
+                    hitmask: ENEMY_HITMASK,
+                    ..default()
+                },
+                health: Health {
+                    hp: 8,
+                    max_hp: 8,
+                    death_sound: audio_clips.light_explosion.clone(),
+                    damage_sound: audio_clips.light_pow.clone(),
+                },
+                weapon: Weapon::new(
+                    BulletType::StandardEnemy,
+                    Vec2::new(20.0, 0.0),
+                    audio_clips.laser_shot.clone(),
+                    1.2,
+                ),
+                camera_shake_on_death: CameraShakeOnDeath { ..default() },
+            },
+            auto_fire: AutoFire {},
+            death_points_awarded: DeathPointsAwarded { points: 100 },
+            turret: Turret {
+                anchor: anchor,
+                offset: offset,
+                turn_rate: 1.5,
+            },
+        };
+    }
+}
+
+// Spawns a turret barrel mounted on `anchor`, with the base model as its child
+pub fn spawn_turret(
+    commands: &mut Commands,
+    anchor: Entity,
+    offset: Vec2,
+    audio_clips: &Res<AudioClipAssets>,
+    models: &Res<SceneAssets>,
+    anchor_position: Vec2,
+) {
+    commands
+        .spawn(TurretBundle::new(
+            anchor,
+            offset,
+            audio_clips,
+            models,
+            anchor_position,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SceneBundle {
+                    scene: models.turret_base.clone(),
+                    ..default()
+                },
+                TurretBase,
+            ));
+        });
+}
+
+// Turrets are separate entities rather than children of their anchor, since
+// collisions are resolved on local transforms. They follow the anchor here
+// and go down with it when it is destroyed or despawned.
+fn update_turrets(
+    mut commands: Commands,
+    time: Res<SimulationTime>,
+    mut turret_query: Query<(Entity, &mut Transform, &Turret)>,
+    anchor_query: Query<&Transform, Without<Turret>>,
+    player_query: Query<&Transform, (With<Player>, Without<Turret>)>,
+) {
+    let player = player_query.iter().next();
+    for (entity, mut transform, turret) in &mut turret_query {
+        let anchor_transform = match anchor_query.get(turret.anchor) {
+            Ok(anchor_transform) => anchor_transform,
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+        let position = anchor_transform.translation.truncate() + turret.offset;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        if let Some(player_transform) = player {
+            transform.rotation = steer_towards(
+                transform.rotation,
+                position,
+                player_transform.translation.truncate(),
+                turret.turn_rate * time.delta_seconds(),
+            );
+        }
+    }
+}
+
+// Counter-rotate the base so only the barrel appears to turn
+fn steady_turret_bases(
+    mut base_query: Query<(&Parent, &mut Transform), With<TurretBase>>,
+    turret_query: Query<&Transform, (With<Turret>, Without<TurretBase>)>,
+) {
+    let resting = Quat::from_rotation_y(std::f32::consts::PI * 0.5);
+    for (parent, mut transform) in &mut base_query {
+        if let Ok(turret_transform) = turret_query.get(parent.get()) {
+            transform.rotation = turret_transform.rotation.inverse() * resting;
+        }
+    }
+}
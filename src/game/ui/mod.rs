@@ -1,10 +1,14 @@
 use super::super::constants::*;
 use super::super::utils::despawn_all;
 use super::super::AppState;
-use super::components::PlayerScoreBoard;
+use super::components::{
+    Bomb, Health, Player, PlayerBombCounter, PlayerHealthBar, PlayerHealthBarRoot,
+    PlayerLivesCounter, PlayerScoreBoard,
+};
 use super::constants::*;
 use super::events::{AudioEvent, ScoreEvent};
-use super::resources::Scoreboard;
+use super::resources::{PlayerLives, ScoreMultiplier, Scoreboard};
+use super::simulation::{SimulationApp, SimulationStep};
 use bevy::prelude::*;
 
 pub struct UiPlugin;
@@ -13,22 +17,38 @@ pub struct UiPlugin;
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Scoreboard { score: 0 })
-            .add_event::<ScoreEvent>()
+            .add_simulation_event::<ScoreEvent>()
             .add_event::<AudioEvent>()
-            .add_system_set(SystemSet::on_enter(AppState::InGame).with_system(setup_scoreboard))
             .add_system_set(
-                SystemSet::on_exit(AppState::InGame).with_system(despawn_all::<PlayerScoreBoard>),
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(reset_score)
+                    .with_system(setup_scoreboard)
+                    .with_system(setup_bomb_counter)
+                    .with_system(setup_health_bar)
+                    .with_system(setup_lives_counter),
             )
-            .add_system(on_score_event);
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(despawn_all::<PlayerScoreBoard>)
+                    .with_system(despawn_all::<PlayerBombCounter>)
+                    .with_system(despawn_all::<PlayerHealthBarRoot>)
+                    .with_system(despawn_all::<PlayerLivesCounter>),
+            )
+            // Scored in the simulation, so a replay adds up to the same score
+            .add_simulation_system(SimulationStep::Despawn, on_score_event)
+            .add_system(update_score_text)
+            .add_system(update_bomb_counter)
+            .add_system(update_lives_counter)
+            .add_system(on_player_health_changed)
+            .add_system(animate_health_bar.after(on_player_health_changed));
     }
 }
 
-fn setup_scoreboard(
-    mut scoreboard: ResMut<Scoreboard>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-) {
+pub fn reset_score(mut scoreboard: ResMut<Scoreboard>) {
     scoreboard.score = 0;
+}
+
+fn setup_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn(
             TextBundle::from_sections([
@@ -65,12 +85,210 @@ fn setup_scoreboard(
 fn on_score_event(
     mut score_events: EventReader<ScoreEvent>,
     mut scoreboard: ResMut<Scoreboard>,
-    mut text_query: Query<&mut Text, With<PlayerScoreBoard>>,
+    score_multiplier: Res<ScoreMultiplier>,
 ) {
     for score_event in score_events.iter() {
-        scoreboard.score += score_event.increment;
-        let mut player_score_text = text_query.single_mut();
-        player_score_text.sections[1].value = scoreboard.score.to_string();
+        scoreboard.score += score_multiplier.apply(score_event.increment);
     }
     score_events.clear(); // Clear buffer to prevent double registration of scoring events (???)
 }
+
+fn update_score_text(
+    scoreboard: Res<Scoreboard>,
+    mut text_query: Query<&mut Text, With<PlayerScoreBoard>>,
+) {
+    if !scoreboard.is_changed() {
+        return;
+    }
+    for mut player_score_text in &mut text_query {
+        player_score_text.sections[1].value = scoreboard.score.to_string();
+    }
+}
+
+fn setup_bomb_counter(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(
+            TextBundle::from_sections([
+                TextSection::new(
+                    "BOMBS: ",
+                    TextStyle {
+                        font: asset_server.load("fonts/Arame-Bold.ttf"),
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: UI_COLOR,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Arame-Bold.ttf"),
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: UI_COLOR,
+                    },
+                ),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(SCOREBOARD_TEXT_PADDING),
+                    left: Val::Px(SCREEN_WIDTH * 0.35),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(PlayerBombCounter);
+}
+
+fn update_bomb_counter(
+    bomb_query: Query<&Bomb, (With<Player>, Changed<Bomb>)>,
+    mut text_query: Query<&mut Text, With<PlayerBombCounter>>,
+) {
+    for bomb in &bomb_query {
+        for mut text in &mut text_query {
+            text.sections[1].value = bomb.charges.to_string();
+        }
+    }
+}
+
+fn setup_health_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(SCOREBOARD_TEXT_PADDING),
+                    left: Val::Px(SCREEN_WIDTH * 0.60),
+                    ..default()
+                },
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(PlayerHealthBarRoot)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "HP ",
+                    TextStyle {
+                        font: asset_server.load("fonts/Arame-Bold.ttf"),
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: UI_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::right(Val::Px(SCOREBOARD_TEXT_PADDING)),
+                    ..default()
+                }),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HEALTH_BAR_WIDTH), Val::Px(HEALTH_BAR_HEIGHT)),
+                        ..default()
+                    },
+                    background_color: HEALTH_BAR_BACKGROUND_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            background_color: HEALTH_BAR_COLOR.into(),
+                            ..default()
+                        })
+                        .insert(PlayerHealthBar::default());
+                });
+        });
+}
+
+fn on_player_health_changed(
+    health_query: Query<&Health, (With<Player>, Changed<Health>)>,
+    mut bar_query: Query<(&mut Style, &mut PlayerHealthBar)>,
+) {
+    for health in &health_query {
+        for (mut style, mut bar) in &mut bar_query {
+            let fraction = (health.hp as f32 / health.max_hp.max(1) as f32).clamp(0.0, 1.0);
+            style.size.width = Val::Percent(100.0 * fraction);
+            if health.hp < bar.last_hp {
+                bar.flash_timer.reset();
+            }
+            bar.last_hp = health.hp;
+        }
+    }
+}
+
+fn animate_health_bar(
+    time: Res<Time>,
+    health_query: Query<&Health, With<Player>>,
+    mut bar_query: Query<(&mut BackgroundColor, &mut PlayerHealthBar)>,
+) {
+    for (mut color, mut bar) in &mut bar_query {
+        bar.flash_timer.tick(time.delta());
+        let low_health = health_query
+            .iter()
+            .any(|health| (health.hp as f32) <= LOW_HEALTH_FRACTION * health.max_hp as f32);
+
+        *color = if !bar.flash_timer.finished() {
+            HEALTH_BAR_FLASH_COLOR.into()
+        } else if low_health {
+            // Pulse while health is low
+            let pulse = 0.5 + 0.5 * (time.elapsed_seconds() * 8.0).sin();
+            let mut low_color = HEALTH_BAR_LOW_COLOR;
+            low_color.set_a(0.4 + 0.6 * pulse);
+            low_color.into()
+        } else {
+            HEALTH_BAR_COLOR.into()
+        };
+    }
+}
+
+fn setup_lives_counter(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(
+            TextBundle::from_sections([
+                TextSection::new(
+                    "LIVES: ",
+                    TextStyle {
+                        font: asset_server.load("fonts/Arame-Bold.ttf"),
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: UI_COLOR,
+                    },
+                ),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/Arame-Bold.ttf"),
+                        font_size: SCOREBOARD_FONT_SIZE,
+                        color: UI_COLOR,
+                    },
+                ),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(SCOREBOARD_TEXT_PADDING + SCOREBOARD_FONT_SIZE * 1.5),
+                    left: Val::Px(SCREEN_WIDTH * 0.60),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(PlayerLivesCounter);
+}
+
+fn update_lives_counter(
+    lives: Res<PlayerLives>,
+    mut text_query: Query<&mut Text, With<PlayerLivesCounter>>,
+    added_query: Query<(), Added<PlayerLivesCounter>>,
+) {
+    if !lives.is_changed() && added_query.is_empty() {
+        return;
+    }
+    for mut text in &mut text_query {
+        text.sections[1].value = lives.lives.to_string();
+    }
+}
@@ -13,12 +13,12 @@ use bevy::{prelude::*, time::FixedTimestep, utils::Duration};
     ShapeDimension, Spawner,
 };*/
 
-use fastrand;
-
 use super::super::AppState;
 use super::components::*;
 use super::constants::*;
 use super::events::*;
+use super::resources::GameRng;
+use super::simulation::SimulationApp;
 
 pub struct VfxPlugin;
 
@@ -26,14 +26,16 @@ impl Plugin for VfxPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(AppState::InGame))
             .add_event::<CameraShakeEvent>()
-            .add_event::<ExplosionEvent>()
+            .add_simulation_event::<ExplosionEvent>()
             //.add_system(on_explosion_event)
-            .add_system(shake_camera);
+            .add_system_set(SystemSet::on_update(AppState::InGame).with_system(shake_camera))
+            .add_system_set(SystemSet::on_exit(AppState::InGame).with_system(reset_camera_shake));
     }
 }
 
 fn shake_camera(
     time: Res<Time>,
+    mut game_rng: ResMut<GameRng>,
     mut shake_events: EventReader<CameraShakeEvent>,
     mut camera_query: Query<(&mut CameraShaker, &mut Transform), With<Camera>>,
 ) {
@@ -54,7 +56,7 @@ fn shake_camera(
         let shake_time = shaker.timer.duration().as_secs_f32() - shaker.timer.elapsed_secs();
 
         if shake_time > 0.01 {
-            let rng = fastrand::Rng::new();
+            let rng = game_rng.cosmetic.fork();
             let magnitude_at_time = shaker.magnitude * shake_time;
             let theta = rng.f32() * 2.0 * PI;
             t.translation = Vec3::new(
@@ -66,6 +68,15 @@ fn shake_camera(
     }
 }
 
+// Leaving the game mid-shake would otherwise leave the camera off-center
+fn reset_camera_shake(mut camera_query: Query<(&mut CameraShaker, &mut Transform), With<Camera>>) {
+    for (mut shaker, mut t) in &mut camera_query {
+        let duration = shaker.timer.duration();
+        shaker.timer.set_elapsed(duration);
+        t.translation = Vec3::new(0.0, 0.0, CAMERA_FAR);
+    }
+}
+
 fn on_explosion_event(
     mut events: EventReader<ExplosionEvent>,
     mut commands: Commands,
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 use crate::utils::despawn_all;
 
 use super::super::AppState;
-use super::components::{Collider, Wall};
+use super::components::{Collider, DamageKind, Wall};
 use super::constants::*;
 pub struct WallPlugin;
 
@@ -89,8 +89,9 @@ impl WallBundle {
                 ..default()
             },
             collider: Collider {
-                rect: location.size(),
+                shape: ColliderShape::Aabb(Vec2::ONE), // scaled up to the wall size
                 damage: 0,
+                damage_kind: DamageKind::Kinetic,
                 hitmask: 0,
             },
             wall: Wall,
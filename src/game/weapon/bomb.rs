@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use super::super::components::*;
+use super::super::constants::*;
+use super::super::controls::{Action, ActionState};
+use super::super::events::*;
+use super::super::AudioClipAssets;
+
+// Inside the arena walls
+fn on_screen(position: Vec3) -> bool {
+    return position.x > LEFT_WALL
+        && position.x < RIGHT_WALL
+        && position.y > BOTTOM_WALL
+        && position.y < TOP_WALL;
+}
+
+// Bomb controller system
+pub fn bomb_controller(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    audio_clips: Res<AudioClipAssets>,
+    mut damage_event: EventWriter<DamageEvent>,
+    mut camera_shake_event: EventWriter<CameraShakeEvent>,
+    mut explosion_event: EventWriter<ExplosionEvent>,
+    mut audio_event: EventWriter<AudioEvent>,
+    mut player_query: Query<(&Transform, &mut Bomb), With<Player>>,
+    enemy_query: Query<
+        (Entity, &Transform, &Collider),
+        (
+            With<Actor>,
+            With<Health>,
+            Without<Player>,
+            Without<Invulnerable>,
+        ),
+    >,
+    bullet_query: Query<(Entity, &Transform, &Collider), With<Bullet>>,
+) {
+    if !actions.just_pressed(Action::Bomb) {
+        return;
+    }
+    for (player_transform, mut bomb) in &mut player_query {
+        if bomb.charges == 0 {
+            continue;
+        }
+        bomb.charges -= 1;
+
+        // Damage every enemy on screen
+        for (entity, transform, collider) in &enemy_query {
+            if collider.hitmask & ENEMY_HITMASK == 0 || !on_screen(transform.translation) {
+                continue;
+            }
+            damage_event.send(DamageEvent {
+                target: entity,
+                amount: BOMB_DAMAGE,
+                kind: DamageKind::Explosive,
+                scored: true,
+            });
+        }
+
+        // Clear enemy bullets
+        for (entity, transform, collider) in &bullet_query {
+            if collider.hitmask & ENEMY_HITMASK != 0 && on_screen(transform.translation) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        camera_shake_event.send(CameraShakeEvent {
+            magnitude: 60.0,
+            duration_secs: 0.6,
+        });
+        explosion_event.send(ExplosionEvent {
+            position: player_transform.translation,
+            lifetime: 0.6,
+            radius: BOMB_BLAST_RADIUS,
+            impulse: BOMB_BLAST_IMPULSE,
+        });
+        audio_event.send(AudioEvent {
+            clip: audio_clips.salt_explosion.clone(),
+        });
+    }
+}
@@ -1,20 +1,25 @@
 use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
-use bevy::{prelude::*, time::FixedTimestep};
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
 
-use super::super::AppState;
 use super::actor::bullet::*;
+use super::ai::steer_towards;
+use super::components::{AimMode, Homing, TimedDespawn, Weapon};
+use super::constants::{MISSILE_LIFETIME, MISSILE_TURN_RATE};
 use super::events::WeaponFiredEvent;
 use super::scene::SceneAssets;
+use super::simulation::{SimulationApp, SimulationStep};
+pub mod bomb;
+use bomb::bomb_controller;
 
 pub struct WeaponPlugin;
 
 impl Plugin for WeaponPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<WeaponFiredEvent>()
-            .add_system(on_bullet_fired)
-            .add_system_set(
-                SystemSet::on_update(AppState::InGame), //.with_run_criteria(FixedTimestep::step(TIME_STEP as f64)),
-            );
+        app.add_simulation_event::<WeaponFiredEvent>()
+            .add_simulation_system(SimulationStep::Weapons, bomb_controller)
+            // Shots fired this step are in the world before collisions are checked
+            .add_simulation_system(SimulationStep::Movement, on_bullet_fired);
     }
 }
 
@@ -35,7 +40,109 @@ fn spawn_bullet(
 ) {
     let bullet_bundle = match weapon_data.bullet_type {
         BulletType::StandardEnemy => StandardEnemyBullet::get_bullet_bundle(models, weapon_data),
+        BulletType::Missile => MissileBullet::get_bullet_bundle(models, weapon_data),
+        BulletType::SineMissile => SineMissileBullet::get_bullet_bundle(models, weapon_data),
         _ => StandardBullet::get_bullet_bundle(models, weapon_data),
     };
-    commands.spawn((bullet_bundle, NotShadowCaster, NotShadowReceiver));
+    let mut bullet = commands.spawn((bullet_bundle, NotShadowCaster, NotShadowReceiver));
+    match weapon_data.bullet_type {
+        BulletType::Missile => {
+            bullet.insert((
+                Homing {
+                    turn_rate: MISSILE_TURN_RATE,
+                },
+                TimedDespawn {
+                    timer: Timer::from_seconds(MISSILE_LIFETIME, TimerMode::Once),
+                },
+            ));
+        }
+        BulletType::SineMissile => {
+            bullet.insert(TimedDespawn {
+                timer: Timer::from_seconds(MISSILE_LIFETIME, TimerMode::Once),
+            });
+        }
+        _ => {}
+    }
+}
+
+// Orientation to fire the next volley with. Target is the player's position and velocity.
+pub fn aimed_transform(
+    weapon: &mut Weapon,
+    transform: &Transform,
+    target: Option<(Vec2, Vec2)>,
+) -> Transform {
+    let mut aimed = *transform;
+    let position = transform.translation.truncate();
+    match (weapon.aim, target) {
+        (AimMode::Forward, _) | (AimMode::Aimed, None) | (AimMode::Predictive, None) => {}
+        (AimMode::Aimed, Some((target_position, _))) => {
+            aimed.rotation = steer_towards(transform.rotation, position, target_position, PI);
+        }
+        (AimMode::Predictive, Some((target_position, target_velocity))) => {
+            let bullet_speed = weapon.bullet_type.speed();
+            let lead = intercept(position, target_position, target_velocity, bullet_speed);
+            aimed.rotation = steer_towards(transform.rotation, position, lead, PI);
+        }
+        (AimMode::Spiral { step }, _) => {
+            aimed.rotation = Quat::from_rotation_z(weapon.spiral_angle) * transform.rotation;
+            weapon.spiral_angle = (weapon.spiral_angle + step) % TAU;
+        }
+    }
+    return aimed;
+}
+
+// Point where a shot fired now at `speed` meets a target moving at constant velocity,
+// or the target's current position when the shot can never catch up
+fn intercept(position: Vec2, target: Vec2, target_velocity: Vec2, speed: f32) -> Vec2 {
+    let offset = target - position;
+    let a = target_velocity.length_squared() - speed * speed;
+    let b = 2.0 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        // Same speed as the target, the quadratic degenerates to a line
+        if b < 0.0 {
+            -c / b
+        } else {
+            -1.0
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            -1.0
+        } else {
+            let root = discriminant.sqrt();
+            let t1 = (-b - root) / (2.0 * a);
+            let t2 = (-b + root) / (2.0 * a);
+            match (t1 > 0.0, t2 > 0.0) {
+                (true, true) => t1.min(t2),
+                (true, false) => t1,
+                (false, true) => t2,
+                (false, false) => -1.0,
+            }
+        }
+    };
+    if time <= 0.0 {
+        return target;
+    }
+    return target + target_velocity * time;
+}
+
+// One event per barrel and projectile of the weapon's firing pattern.
+// Barrel offsets are relative to the firer: x along its forward direction, y to its side.
+pub fn volley_events(weapon: &Weapon, transform: &Transform, hitmask: u8) -> Vec<WeaponFiredEvent> {
+    let forward = transform.forward().truncate().normalize_or_zero();
+    let side = forward.perp();
+    let mut events = Vec::new();
+    for offset in &weapon.pattern.barrels {
+        let translation = transform.translation.truncate() + forward * offset.x + side * offset.y;
+        for angle in weapon.pattern.angles() {
+            events.push(WeaponFiredEvent {
+                bullet_type: weapon.bullet_type.clone(),
+                translation: translation,
+                rotation: Quat::from_rotation_z(angle) * transform.rotation,
+                hitmask: hitmask,
+            });
+        }
+    }
+    return events;
 }
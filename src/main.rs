@@ -4,7 +4,7 @@ mod menus;
 use menus::MenuPlugin;
 
 mod game;
-use game::{components::CameraShaker, GamePlugin, SceneAssets};
+use game::{components::CameraShaker, GamePlugin, ReplayMode, RunSeed, SceneAssets};
 
 mod constants;
 use constants::{CAMERA_FAR, SCREEN_HEIGHT, SCREEN_WIDTH};
@@ -18,8 +18,26 @@ enum AppState {
     Paused,
 }
 fn main() {
+    // The arguments are read before the App and its LogPlugin exist, so errors go to stderr
+    let replay_mode = match ReplayMode::from_args(std::env::args()) {
+        Ok(replay_mode) => replay_mode,
+        Err(e) => {
+            eprintln!("Could not load the replay: {}", e);
+            return;
+        }
+    };
+    // A replay is played back with its own seed
+    let run_seed = match replay_mode.run_seed() {
+        Some(run_seed) => run_seed,
+        None => RunSeed::from_args(std::env::args()).unwrap_or_else(|e| {
+            eprintln!("{}, using a random seed", e);
+            return RunSeed::Random;
+        }),
+    };
     App::new()
         .add_state(AppState::Menu)
+        .insert_resource(run_seed)
+        .insert_resource(replay_mode)
         .insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.27)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
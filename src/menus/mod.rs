@@ -1,7 +1,11 @@
 // Adapted from https://github.com/bevyengine/bevy/blob/v0.8.1/examples/games/game_menu.rs
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{game::SceneAssets, utils::despawn_all};
+use crate::{
+    game::controls::{Action, InputBindings},
+    game::SceneAssets,
+    utils::despawn_all,
+};
 
 use super::AppState;
 
@@ -11,13 +15,14 @@ const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 const BOX_COLOR: Color = Color::rgba(0.25, 0.0, 0.0, 0.06);
-const LEVEL_END_BOX_COLOR: Color = Color::rgba(0.0, 0.0, 0.8, 1.0);
+const CAMPAIGN_COMPLETE_BOX_COLOR: Color = Color::rgba(0.0, 0.0, 0.8, 1.0);
 const PLAYER_DEATH_BOX_COLOR: Color = Color::rgba(0.25, 0.0, 0.0, 1.0);
+const PAUSE_BOX_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub enum MenuState {
     Main,
-    LevelEnd,
+    CampaignComplete,
     PlayerDeath,
     Disabled,
 }
@@ -28,10 +33,13 @@ pub struct MenuBackground;
 pub struct OnMainMenuScreen;
 
 #[derive(Component)]
-pub struct OnLevelEndScreen;
+pub struct OnCampaignCompleteScreen;
 
 #[derive(Component)]
 pub struct OnPlayerDeathScreen;
+
+#[derive(Component)]
+pub struct OnPauseScreen;
 pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
@@ -46,10 +54,13 @@ impl Plugin for MenuPlugin {
             .add_system_set(
                 SystemSet::on_exit(MenuState::Main).with_system(despawn_all::<OnMainMenuScreen>),
             )
-            .add_system_set(SystemSet::on_enter(MenuState::LevelEnd).with_system(level_end_setup))
             .add_system_set(
-                SystemSet::on_exit(MenuState::LevelEnd)
-                    .with_system(despawn_all::<OnLevelEndScreen>),
+                SystemSet::on_enter(MenuState::CampaignComplete)
+                    .with_system(campaign_complete_setup),
+            )
+            .add_system_set(
+                SystemSet::on_exit(MenuState::CampaignComplete)
+                    .with_system(despawn_all::<OnCampaignCompleteScreen>),
             )
             .add_system_set(
                 SystemSet::on_enter(MenuState::PlayerDeath).with_system(player_death_setup),
@@ -58,11 +69,21 @@ impl Plugin for MenuPlugin {
                 SystemSet::on_exit(MenuState::PlayerDeath)
                     .with_system(despawn_all::<OnPlayerDeathScreen>),
             )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(pause_menu_setup))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused).with_system(despawn_all::<OnPauseScreen>),
+            )
             .add_system_set(
                 SystemSet::on_update(AppState::Menu)
                     .with_system(menu_action)
                     .with_system(button_system),
             )
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused)
+                    .with_system(menu_action)
+                    .with_system(button_system),
+            )
+            .add_system(toggle_pause)
             .add_system_set(
                 SystemSet::on_exit(AppState::Menu).with_system(despawn_all::<MenuBackground>),
             );
@@ -82,6 +103,7 @@ enum MenuButtonAction {
     Quit,
     MainMenu,
     Restart,
+    Resume,
 }
 // This system handles changing all buttons color based on mouse interaction
 fn button_system(
@@ -147,11 +169,26 @@ fn menu_action(
                 MenuButtonAction::Quit => app_exit_events.send(AppExit),
                 MenuButtonAction::MainMenu => {
                     menu_state.overwrite_set(MenuState::Main).unwrap();
-                    //game_state.overwrite_set(AppState::Menu).unwrap();// PANICS for some reason
+                    // The death and campaign screens are already in AppState::Menu,
+                    // setting it again fails with StateError::AlreadyInState
+                    if *game_state.current() == AppState::Paused {
+                        // Replacing the whole stack also exits the paused InGame state
+                        game_state.replace(AppState::Menu).unwrap();
+                    }
                 }
                 MenuButtonAction::Restart => {
-                    menu_state.overwrite_set(MenuState::Main).unwrap();
-                    //game_state.overwrite_set(AppState::Menu).unwrap();// PANICS for some reason
+                    if *game_state.current() == AppState::Paused {
+                        // Exits the paused level and enters a fresh one
+                        game_state.replace(AppState::InGame).unwrap();
+                    } else {
+                        // Entering InGame resets the score, lives and level
+                        // and spawns a new player
+                        menu_state.overwrite_set(MenuState::Disabled).unwrap();
+                        game_state.overwrite_set(AppState::InGame).unwrap();
+                    }
+                }
+                MenuButtonAction::Resume => {
+                    game_state.pop().unwrap();
                 }
                 MenuButtonAction::Play => {
                     menu_state.overwrite_set(MenuState::Disabled).unwrap();
@@ -162,6 +199,25 @@ fn menu_action(
     }
 }
 
+// Escape or P pauses the game, pressing either again resumes it.
+// Pausing pushes on top of InGame so its on_exit cleanup does not run.
+fn toggle_pause(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    mut game_state: ResMut<State<AppState>>,
+) {
+    if !bindings.just_pressed(Action::Pause, &keyboard_input, &gamepads, &button_input) {
+        return;
+    }
+    match game_state.current() {
+        AppState::InGame => game_state.overwrite_push(AppState::Paused).unwrap(),
+        AppState::Paused => game_state.overwrite_pop().unwrap(),
+        AppState::Menu => {}
+    }
+}
+
 fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/Arame-Bold.ttf");
     // Common style for all buttons on the screen
@@ -253,7 +309,7 @@ fn main_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
-fn level_end_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn campaign_complete_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/Arame-Bold.ttf");
     // Common style for all buttons on the screen
     let button_style = Style {
@@ -278,15 +334,15 @@ fn level_end_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 align_items: AlignItems::Center,
                 ..default()
             },
-            background_color: LEVEL_END_BOX_COLOR.into(),
+            background_color: CAMPAIGN_COMPLETE_BOX_COLOR.into(),
             ..default()
         })
-        .insert(OnLevelEndScreen)
+        .insert(OnCampaignCompleteScreen)
         .with_children(|parent| {
             // Display the game name
             parent.spawn(
                 TextBundle::from_section(
-                    "LEVEL END",
+                    "CAMPAIGN COMPLETE",
                     TextStyle {
                         font: font.clone(),
                         font_size: 80.0,
@@ -314,6 +370,18 @@ fn level_end_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         button_text_style.clone(),
                     ));
                 });
+            parent
+                .spawn(ButtonBundle {
+                    style: button_style.clone(),
+                    ..default()
+                })
+                .insert(MenuButtonAction::Restart)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "RESTART",
+                        button_text_style.clone(),
+                    ));
+                });
         });
 }
 
@@ -347,22 +415,18 @@ fn player_death_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
         })
         .insert(OnPlayerDeathScreen)
         .with_children(|parent| {
-            /*
             parent
                 .spawn(ButtonBundle {
-                    style: button_style,
+                    style: button_style.clone(),
                     ..default()
                 })
                 .insert(MenuButtonAction::Restart)
                 .with_children(|parent| {
-                    let icon = asset_server.load("textures/Game Icons/exitRight.png");
-                    parent.spawn(ImageBundle {
-                        style: button_icon_style,
-                        image: UiImage(icon),
-                        ..default()
-                    });
-                    parent.spawn(TextBundle::from_section("RESTART", button_text_style));
-                });*/
+                    parent.spawn(TextBundle::from_section(
+                        "RESTART",
+                        button_text_style.clone(),
+                    ));
+                });
 
             parent
                 .spawn(ButtonBundle {
@@ -400,3 +464,245 @@ fn player_death_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             );
         });
 }
+
+fn pause_menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Arame-Bold.ttf");
+    // Common style for all buttons on the screen
+    let button_style = Style {
+        size: Size::new(Val::Px(300.0), Val::Px(65.0)),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let button_text_style = TextStyle {
+        font: font.clone(),
+        font_size: 40.0,
+        color: TEXT_COLOR,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: PAUSE_BOX_COLOR.into(),
+            ..default()
+        })
+        .insert(OnPauseScreen)
+        .with_children(|parent| {
+            // Display
+            // - MAIN MENU
+            // - RESTART
+            // - RESUME
+            for (action, label) in [
+                (MenuButtonAction::MainMenu, "MAIN MENU"),
+                (MenuButtonAction::Restart, "RESTART"),
+                (MenuButtonAction::Resume, "RESUME"),
+            ] {
+                parent
+                    .spawn(ButtonBundle {
+                        style: button_style.clone(),
+                        ..default()
+                    })
+                    .insert(action)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+                    });
+            }
+
+            parent.spawn(
+                TextBundle::from_section(
+                    "PAUSED",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 80.0,
+                        color: TEXT_COLOR,
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(50.0)),
+                    ..default()
+                }),
+            );
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{asset::AssetPlugin, input::InputPlugin};
+    use std::{thread, time::Duration};
+
+    use crate::game::components::{AiActorSpawner, Player};
+    use crate::game::constants::{PLAYER_LIVES, TIME_STEP};
+    use crate::game::controls::ActionState;
+    use crate::game::events::PlayerDeathEvent;
+    use crate::game::player::{reset_lives, PlayerPlugin};
+    use crate::game::resources::{PlayerLives, ScoreMultiplier, Scoreboard, SimulationTime};
+    use crate::game::ui::{reset_score, UiPlugin};
+    use crate::game::{
+        setup_resources as load_audio_clips, LevelPlugin, RngPlugin, SimulationPlugin,
+    };
+
+    // The state machine and run reset of the game, without windows, assets or rendering
+    fn menu_app(game_state: AppState, menu_state: MenuState) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<AppExit>()
+            .add_state(game_state)
+            .add_state(menu_state)
+            .insert_resource(PlayerLives { lives: 0 })
+            .insert_resource(Scoreboard { score: 1200 })
+            .add_system_set(SystemSet::on_update(AppState::Menu).with_system(menu_action))
+            .add_system_set(SystemSet::on_update(AppState::Paused).with_system(menu_action))
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(reset_lives)
+                    .with_system(reset_score),
+            );
+        return app;
+    }
+
+    // The menus on top of the player, level and simulation plugins, with assets
+    // loaded from disk but nothing rendered or played
+    fn game_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_event::<AppExit>()
+            .add_state(AppState::Menu)
+            .init_resource::<ActionState>()
+            .init_resource::<InputBindings>()
+            .init_resource::<ScoreMultiplier>()
+            .add_startup_system(load_audio_clips)
+            .add_plugin(SimulationPlugin)
+            .add_plugin(RngPlugin)
+            .add_plugin(UiPlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(MenuPlugin);
+        return app;
+    }
+
+    // One simulation step, however little real time the frame took
+    fn step(app: &mut App) {
+        app.world.resource_mut::<SimulationTime>().accumulator += TIME_STEP as f64;
+        app.update();
+    }
+
+    fn count<T: Component>(app: &mut App) -> usize {
+        return app
+            .world
+            .query_filtered::<(), With<T>>()
+            .iter(&app.world)
+            .count();
+    }
+
+    // Levels are loaded in the background, their spawners show up a few steps in
+    fn wait_for_spawners(app: &mut App) {
+        for _ in 0..500 {
+            if count::<AiActorSpawner>(app) > 0 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+            step(app);
+        }
+        panic!("the first level never started");
+    }
+
+    // Clicks a button on the current screen
+    fn press(app: &mut App, action: MenuButtonAction) {
+        let mut query = app
+            .world
+            .query_filtered::<(&mut Interaction, &MenuButtonAction), With<Button>>();
+        let mut pressed = false;
+        for (mut interaction, button_action) in query.iter_mut(&mut app.world) {
+            if std::mem::discriminant(button_action) == std::mem::discriminant(&action) {
+                *interaction = Interaction::Clicked;
+                pressed = true;
+            }
+        }
+        assert!(pressed, "no such button on the screen");
+        app.update();
+    }
+
+    fn click(app: &mut App, action: MenuButtonAction) {
+        app.world.spawn((Button, Interaction::Clicked, action));
+        app.update();
+    }
+
+    #[test]
+    fn restart_after_death_starts_a_fresh_run() {
+        let mut app = game_app();
+        app.update();
+        press(&mut app, MenuButtonAction::Play);
+        wait_for_spawners(&mut app);
+
+        // Last life lost, the way report_player_death sees it
+        app.world.resource_mut::<PlayerLives>().lives = 1;
+        app.world.resource_mut::<Scoreboard>().score = 1200;
+        let players: Vec<Entity> = app
+            .world
+            .query_filtered::<Entity, With<Player>>()
+            .iter(&app.world)
+            .collect();
+        for player in players {
+            app.world.despawn(player);
+        }
+        app.world
+            .resource_mut::<Events<PlayerDeathEvent>>()
+            .send(PlayerDeathEvent { bomb_charges: 0 });
+        step(&mut app);
+
+        assert_eq!(
+            *app.world.resource::<State<AppState>>().current(),
+            AppState::Menu
+        );
+        assert_eq!(
+            *app.world.resource::<State<MenuState>>().current(),
+            MenuState::PlayerDeath
+        );
+        assert_eq!(count::<OnPlayerDeathScreen>(&mut app), 1);
+        assert_eq!(count::<AiActorSpawner>(&mut app), 0);
+
+        press(&mut app, MenuButtonAction::Restart);
+
+        let game_state = app.world.resource::<State<AppState>>();
+        assert_eq!(*game_state.current(), AppState::InGame);
+        assert_eq!(game_state.inactives().len(), 0);
+        let menu_state = app.world.resource::<State<MenuState>>();
+        assert_eq!(*menu_state.current(), MenuState::Disabled);
+        assert_eq!(app.world.resource::<PlayerLives>().lives, PLAYER_LIVES);
+        assert_eq!(app.world.resource::<Scoreboard>().score, 0);
+        assert_eq!(count::<OnPlayerDeathScreen>(&mut app), 0);
+        assert_eq!(count::<Player>(&mut app), 1);
+        wait_for_spawners(&mut app);
+    }
+
+    #[test]
+    fn restart_while_paused_leaves_the_paused_run() {
+        let mut app = menu_app(AppState::InGame, MenuState::Disabled);
+        app.update();
+        app.world.resource_mut::<PlayerLives>().lives = 1;
+        app.world.resource_mut::<Scoreboard>().score = 50;
+        app.world
+            .resource_mut::<State<AppState>>()
+            .push(AppState::Paused)
+            .unwrap();
+        app.update();
+
+        click(&mut app, MenuButtonAction::Restart);
+
+        let game_state = app.world.resource::<State<AppState>>();
+        assert_eq!(*game_state.current(), AppState::InGame);
+        assert_eq!(game_state.inactives().len(), 0);
+        assert_eq!(app.world.resource::<PlayerLives>().lives, PLAYER_LIVES);
+        assert_eq!(app.world.resource::<Scoreboard>().score, 0);
+    }
+}